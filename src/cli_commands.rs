@@ -1,4 +1,8 @@
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{ArgGroup, Args, Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::query::parse_age;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -7,6 +11,14 @@ pub struct Cli {
     #[arg(long)]
     pub autocomplete: bool,
 
+    /// Directory with config.yml, created if it doesn't exist. Defaults to ~/.config/hoard
+    #[arg(long, global = true, value_name = "DIR", env = "HOARD_HOME")]
+    pub config: Option<PathBuf>,
+
+    /// Trove file to use instead of the one set in config.yml, created if it doesn't exist
+    #[arg(long, global = true, value_name = "FILE", env = "HOARD_TROVE")]
+    pub trove: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -27,6 +39,111 @@ pub enum Mode {
     Revert,
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SubscriptionAction {
+    /// Fetch all subscribed troves, regardless of their refresh interval
+    Update,
+    /// List all subscribed troves and when they were fetched
+    List,
+}
+
+/// How to handle an incoming command with the same name and namespace as a local one
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ConflictStrategy {
+    /// Replace the local command with the incoming one
+    Replace,
+    /// Keep the local command and ignore the incoming one
+    Keep,
+    /// Keep both, the incoming command gets a random name suffix
+    Rename,
+    /// Ask for every conflict
+    Ask,
+    /// Keep whichever command was modified last
+    Newest,
+}
+
+/// Field to sort listed commands by
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SortField {
+    Name,
+    Namespace,
+    /// When the command was saved
+    Created,
+    /// When the command was last changed
+    Modified,
+    /// When the command was last selected
+    #[value(alias = "last_used")]
+    LastUsed,
+    /// How often the command was selected
+    #[value(alias = "usage_count")]
+    UsageCount,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum SortOrder {
+    /// Ascending, A to Z, oldest or least used first
+    Asc,
+    /// Descending, Z to A, newest or most used first
+    Desc,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Format {
+    /// hoard trove file
+    Trove,
+    /// navi cheatsheet (.cheat)
+    Navi,
+    /// pet snippet file (snippet.toml)
+    Pet,
+    /// justfile of the just command runner
+    Just,
+    /// Makefile, import only
+    Make,
+    /// bash functions to source, export only
+    Bash,
+    /// zsh functions to source, export only
+    Zsh,
+    /// fish functions to source, export only
+    FishFunctions,
+}
+
+impl Format {
+    /// Shell whose functions the format consists of, if any
+    pub const fn shell(self) -> Option<Shell> {
+        match self {
+            Self::Bash => Some(Shell::Bash),
+            Self::Zsh => Some(Shell::Zsh),
+            Self::FishFunctions => Some(Shell::Fish),
+            Self::Trove | Self::Navi | Self::Pet | Self::Just | Self::Make => None,
+        }
+    }
+}
+
+/// Output of `hoard list` for other programs
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum ListFormat {
+    /// JSON document with a schema version and all metadata of the commands
+    Json,
+    /// JSON Lines, one JSON object per command, for streaming into jq or fzf
+    #[value(alias = "json-lines")]
+    Jsonl,
+    /// hoard trove file
+    Yaml,
+    /// Tab separated values with a header line
+    Tsv,
+    /// Table for humans
+    Table,
+    /// Only the command names, one per line
+    Names,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
 #[derive(Subcommand)]
 pub enum Commands {
     /// Shows setting file paths
@@ -49,21 +166,53 @@ pub enum Commands {
         /// [Optional] Description of what the command does
         #[arg(short = 'd', long, value_name = "DESCRIPTION")]
         description: Option<String>,
+
+        /// [Optional] Namespace of the new command. It is not asked for if set
+        #[arg(long, value_name = "NAMESPACE")]
+        namespace: Option<String>,
+
+        /// Mark the new command as favorite
+        #[arg(long)]
+        favorite: bool,
+
+        /// Don't ask for anything. The namespace defaults to the configured default namespace and the name is derived from the description or command
+        #[arg(short = 'y', long, visible_alias = "no-prompt")]
+        yes: bool,
+
+        /// Read the command from standard input, like `echo "git status" | hoard new --from-stdin`. Implies `--yes`
+        #[arg(long, conflicts_with = "command")]
+        from_stdin: bool,
     },
 
     /// List commands saved in trove.
     List {
-        /// Apply filter to listed commands
-        #[arg(short = 'f', long)]
+        /// Apply filter to listed commands, like `ns:git tag:remote -tag:deprecated push used:>5`
+        #[arg(short = 'f', long, value_name = "QUERY")]
         filter: Option<String>,
 
-        /// Return hoarded commands in structured format
-        #[arg(short = 'j', long)]
+        /// Print the commands in this format instead of opening the interactive list
+        #[arg(long, value_enum, value_name = "FORMAT")]
+        format: Option<ListFormat>,
+
+        /// Shortcut for `--format json`
+        #[arg(short = 'j', long, conflicts_with_all = ["format", "simple"])]
         json: bool,
 
-        /// Return hoarded commands in a simplified table view
-        #[arg(short = 's', long)]
+        /// Sort commands by this field instead of ranking them
+        #[arg(long, value_name = "FIELD")]
+        sort: Option<SortField>,
+
+        /// Sort order. Defaults to ascending for names and descending for dates and usage count
+        #[arg(long, requires = "sort")]
+        order: Option<SortOrder>,
+
+        /// Shortcut for `--format table`
+        #[arg(short = 's', long, conflicts_with = "format")]
         simple: bool,
+
+        /// Copy the command selected in the interactive list to the clipboard instead of printing it
+        #[arg(short = 'c', long, conflicts_with_all = ["format", "json", "simple"])]
+        copy: bool,
     },
 
     /// Pick a command of the trove and print it
@@ -71,6 +220,25 @@ pub enum Commands {
         /// Name of the command to print
         #[arg(short = 'n', long)]
         name: String,
+
+        /// Copy the command to the clipboard instead of printing it
+        #[arg(short = 'c', long)]
+        copy: bool,
+    },
+
+    /// Show a command with all its metadata
+    Show {
+        /// Name of the command to show
+        #[arg(short = 'n', long)]
+        name: String,
+
+        /// [Optional] Namespace of the command, if several namespaces have a command with this name
+        #[arg(long)]
+        namespace: Option<String>,
+
+        /// Print the command as JSON
+        #[arg(short = 'j', long)]
+        json: bool,
     },
 
     /// Set a custom parameter token
@@ -99,20 +267,81 @@ pub enum Commands {
         /// URL or path to .trove file to import
         #[arg(long)]
         uri: String,
+
+        /// Format of the file to import
+        #[arg(long, value_enum, default_value_t = Format::Trove)]
+        format: Format,
+
+        /// [Optional] Namespace to import all commands into. Formats without namespaces use your default namespace otherwise
+        #[arg(short = 'n', long)]
+        namespace: Option<String>,
+
+        /// How to handle imported commands with the same name as a local one
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Replace)]
+        on_conflict: ConflictStrategy,
+
+        /// Show what the import would change without touching your trove
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Print the dry run report as JSON
+        #[arg(long, requires = "dry_run")]
+        json: bool,
+    },
+
+    /// Pick frequently used commands from your shell history and hoard them
+    ImportHistory {
+        /// [Optional] Shell which wrote the history file. Detected from $SHELL if not set
+        #[arg(short = 's', long, value_enum)]
+        shell: Option<Shell>,
+
+        /// [Optional] Path to the history file. Defaults to the history file of the shell
+        #[arg(short = 'p', long)]
+        path: Option<String>,
+
+        /// Maximum number of commands to suggest
+        #[arg(short = 'l', long, default_value_t = 50)]
+        limit: usize,
     },
 
     /// Export a trove file
+    ///
+    /// Without any selection flags, you are asked which commands to export
     Export {
         /// Path where the trove file should be saved to
-        #[arg(long)]
-        path: String,
+        #[arg(long, required_unless_present = "stdout")]
+        path: Option<String>,
+
+        /// Format of the exported file
+        #[arg(long, value_enum, default_value_t = Format::Trove)]
+        format: Format,
+
+        /// Export all commands without asking
+        #[arg(short = 'a', long, conflicts_with_all = ["namespace", "tag", "filter", "favorites"])]
+        all: bool,
+
+        #[command(flatten)]
+        selection: SelectionArgs,
+
+        /// Write the export to standard output instead of a file
+        #[arg(long, conflicts_with = "path")]
+        stdout: bool,
     },
 
-    /// Edit a saved command
+    /// Edit a saved command, or many at once as YAML in your editor
     Edit {
         /// Name of the command to edit
-        #[arg(short = 'n', long)]
-        name: String,
+        #[arg(short = 'n', long, required_unless_present = "editor")]
+        name: Option<String>,
+
+        /// Edit the commands as YAML in `$VISUAL` or `$EDITOR` instead of answering prompts
+        #[arg(short = 'e', long)]
+        editor: bool,
+
+        /// Commands to edit as `<namespace>/<name>` or just `<name>`, or whole namespaces.
+        /// All your commands if none are selected
+        #[arg(requires = "editor", value_name = "COMMAND")]
+        selection: Vec<String>,
     },
 
     /// Print shell config
@@ -127,5 +356,219 @@ pub enum Commands {
         ///
         #[arg(value_enum)]
         command: Mode,
+
+        /// How `hoard sync get` handles online commands with the same name as a local one
+        #[arg(long, value_enum, default_value_t = ConflictStrategy::Replace)]
+        on_conflict: ConflictStrategy,
     },
+
+    /// Manage troves you subscribed to in your config file. Subscribed commands are read-only
+    Subscriptions {
+        #[arg(value_enum)]
+        command: SubscriptionAction,
+    },
+
+    /// Add, remove or rename a tag of many commands at once
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    /// List, rename and merge namespaces
+    Namespace {
+        #[command(subcommand)]
+        action: NamespaceAction,
+    },
+
+    /// Show how your commands are used: per namespace and tag, most used, never used and stale commands
+    Stats {
+        /// Number of most used commands to show
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+
+        /// Commands not used for this long are stale, like `90d`, `12w` or `1y`
+        #[arg(long, value_name = "AGE", value_parser = age, default_value = "90d")]
+        stale_after: Duration,
+
+        /// Only count commands matching the filter query
+        #[arg(short = 'f', long, value_name = "QUERY")]
+        filter: Option<String>,
+
+        /// Print the statistics as JSON
+        #[arg(short = 'j', long)]
+        json: bool,
+    },
+
+    /// Print a completion script for your shell. It completes command names, namespaces and tags from your trove,
+    /// e.g. `source <(hoard completions bash)`
+    Completions {
+        /// Shell to print the completion script for
+        #[arg(value_enum, required_unless_present = "complete")]
+        shell: Option<Shell>,
+
+        /// Print the values to complete for the last of `words`. Used by the completion scripts
+        #[arg(long, hide = true, conflicts_with = "shell")]
+        complete: bool,
+
+        /// Arguments typed after `hoard`, the last one is completed
+        #[arg(last = true, hide = true, requires = "complete")]
+        words: Vec<String>,
+    },
+
+    /// Move commands into another namespace. Names already taken there are numbered, like `status_2`
+    Mv {
+        /// Commands to move, as `<namespace>/<name>` or just `<name>` if no other namespace uses it,
+        /// followed by the namespace to move them into, like `hoard mv git/status jj/status vcs`
+        #[arg(required = true, value_name = "COMMAND")]
+        arguments: Vec<String>,
+
+        #[command(flatten)]
+        selection: SelectionArgs,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Remove stale and duplicate commands. Favorites are only removed as duplicates
+    #[command(group(ArgGroup::new("criteria").required(true).multiple(true)))]
+    Prune {
+        /// Remove commands not used for this long, like `180d`, `26w` or `1y`
+        #[arg(long, value_name = "AGE", value_parser = age, group = "criteria")]
+        unused_for: Option<Duration>,
+
+        /// Remove commands which were never used
+        #[arg(long, group = "criteria")]
+        never_used: bool,
+
+        /// Remove commands with the same command text as another one, in any namespace.
+        /// The favorite, most used or oldest one is kept
+        #[arg(long, group = "criteria")]
+        duplicates: bool,
+
+        /// Like `--duplicates`, but whitespace and the order of flags don't matter
+        #[arg(long, group = "criteria")]
+        near_duplicates: bool,
+
+        /// Remove the commands without asking to confirm them
+        #[arg(short = 'y', long)]
+        yes: bool,
+
+        /// Only print what would be removed
+        #[arg(long, conflicts_with = "yes")]
+        dry_run: bool,
+    },
+}
+
+/// Parse an age argument like `90d`
+fn age(text: &str) -> Result<Duration, String> {
+    parse_age(text)
+        .ok_or_else(|| format!("expected an age like '30d', '12h', '2w' or '1y', got '{text}'"))
+}
+
+/// Commands to work on, selected by flags. Criteria of different flags must all match
+#[derive(Debug, Args)]
+pub struct SelectionArgs {
+    /// Only commands of this namespace. Can be used multiple times
+    #[arg(short = 'n', long, value_name = "NAMESPACE")]
+    pub namespace: Vec<String>,
+
+    /// Only commands with this tag. Can be used multiple times
+    #[arg(short = 't', long, value_name = "TAG")]
+    pub tag: Vec<String>,
+
+    /// Only commands matching the filter query
+    #[arg(short = 'f', long, value_name = "QUERY")]
+    pub filter: Option<String>,
+
+    /// Only favorite commands
+    #[arg(long)]
+    pub favorites: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum TagAction {
+    /// Add a tag to the selected commands
+    Add {
+        /// Tag to add
+        #[arg(value_name = "TAG")]
+        name: String,
+
+        #[command(flatten)]
+        selection: SelectionArgs,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Remove a tag from the selected commands, or from all commands if none are selected
+    Remove {
+        /// Tag to remove
+        #[arg(value_name = "TAG")]
+        name: String,
+
+        #[command(flatten)]
+        selection: SelectionArgs,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Rename a tag of the selected commands, or of all commands if none are selected
+    Rename {
+        /// Tag to rename
+        old: String,
+
+        /// New name of the tag
+        new: String,
+
+        #[command(flatten)]
+        selection: SelectionArgs,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum NamespaceAction {
+    /// List all namespaces with their number of commands
+    List,
+    /// Rename a namespace. To move its commands into an existing namespace, use `merge`
+    Rename {
+        /// Namespace to rename
+        old: String,
+
+        /// New name of the namespace
+        new: String,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Move all commands of namespaces into another namespace. Names already taken there are numbered
+    Merge {
+        /// Namespaces to merge
+        #[arg(required = true)]
+        namespaces: Vec<String>,
+
+        /// Namespace to merge them into
+        into: String,
+
+        /// Only print what would change
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+#[cfg(test)]
+mod test_cli {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn valid_arguments() {
+        Cli::command().debug_assert();
+    }
 }
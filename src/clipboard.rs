@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use base64::engine::general_purpose;
+use base64::Engine as _;
+use std::env;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Clipboard programs used if there is no terminal for OSC 52 and their display server runs:
+/// environment variable of the display server, program and arguments
+const CLIPBOARD_TOOLS: [(&str, &str, &[&str]); 2] = [
+    ("WAYLAND_DISPLAY", "wl-copy", &[]),
+    ("DISPLAY", "xclip", &["-selection", "clipboard"]),
+];
+
+/// OSC 52 escape sequence asking the terminal to put `text` into the system clipboard.
+/// Inside tmux the sequence is wrapped to be passed through to the terminal tmux runs in
+pub fn osc52(text: &str, is_tmux: bool) -> String {
+    let sequence = format!("\x1b]52;c;{}\x07", general_purpose::STANDARD.encode(text));
+    if is_tmux {
+        format!("\x1bPtmux;{}\x1b\\", sequence.replace('\x1b', "\x1b\x1b"))
+    } else {
+        sequence
+    }
+}
+
+/// Whether `program` is installed and copied `text`
+fn copy_with_tool(program: &str, args: &[&str], text: &str) -> bool {
+    let Ok(mut child) = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return false;
+    };
+    // Closing stdin when it is dropped lets the program know the text is complete
+    let is_written = child
+        .stdin
+        .take()
+        .is_some_and(|mut stdin| stdin.write_all(text.as_bytes()).is_ok());
+    child.wait().is_ok_and(|status| status.success()) && is_written
+}
+
+/// Copy `text` to the clipboard through the terminal with OSC 52, which works over SSH and in tmux.
+/// Without a terminal, `wl-copy` or `xclip` copy it if one of them is installed.
+///
+/// Returns an error if there is neither a terminal nor a clipboard program.
+/// Whether the terminal supports OSC 52 can't be known
+pub fn copy(text: &str) -> Result<()> {
+    let is_sent_to_terminal = termion::get_tty()
+        .and_then(|mut tty| {
+            tty.write_all(osc52(text, env::var_os("TMUX").is_some()).as_bytes())?;
+            tty.flush()
+        })
+        .is_ok();
+    let is_copied = is_sent_to_terminal
+        || CLIPBOARD_TOOLS
+            .iter()
+            .filter(|(display, ..)| env::var_os(display).is_some())
+            .any(|(_, program, args)| copy_with_tool(program, args, text));
+    if is_copied {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Could not copy to the clipboard: no terminal, wl-copy or xclip found"
+        ))
+    }
+}
+
+#[cfg(test)]
+mod test_clipboard {
+    use super::*;
+
+    #[test]
+    fn osc52_sequences() {
+        assert_eq!("\x1b]52;c;Z2l0IHN0YXR1cw==\x07", osc52("git status", false));
+        assert_eq!(
+            "\x1bPtmux;\x1b\x1b]52;c;Z2l0IHN0YXR1cw==\x07\x1b\\",
+            osc52("git status", true)
+        );
+    }
+
+    #[test]
+    fn missing_tool() {
+        assert!(!copy_with_tool("hoard-no-such-clipboard", &[], "text"));
+    }
+}
@@ -0,0 +1,295 @@
+use clap::{Arg, Command};
+use clap_complete::Shell as CompletionShell;
+use std::collections::BTreeSet;
+
+use crate::cli_commands::Shell;
+use crate::core::trove::Trove;
+
+/// Values of an argument which come from the trove
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum TroveValues {
+    /// Command names
+    Names,
+    /// Commands as `<namespace>/<name>`, and namespaces
+    References,
+    Namespaces,
+    Tags,
+}
+
+/// Arguments completed from the trove which are not named after their values:
+/// subcommands leading to the argument, argument id and values
+const NAMED_ARGUMENTS: [(&[&str], &str, TroveValues); 9] = [
+    (&["pick"], "name", TroveValues::Names),
+    (&["show"], "name", TroveValues::Names),
+    (&["edit"], "name", TroveValues::Names),
+    (&["edit"], "selection", TroveValues::References),
+    (&["remove"], "name", TroveValues::Names),
+    (&["tag", "remove"], "name", TroveValues::Tags),
+    (&["tag", "rename"], "old", TroveValues::Tags),
+    (&["namespace", "rename"], "old", TroveValues::Namespaces),
+    (&["mv"], "arguments", TroveValues::References),
+];
+
+fn trove_values(path: &[&str], arg: &Arg) -> Option<TroveValues> {
+    let id = arg.get_id().as_str();
+    let named = NAMED_ARGUMENTS
+        .iter()
+        .find(|(arg_path, arg_id, _)| *arg_path == path && *arg_id == id)
+        .map(|(_, _, values)| *values);
+    named.or(match id {
+        "namespace" | "namespaces" | "into" => Some(TroveValues::Namespaces),
+        "tag" => Some(TroveValues::Tags),
+        _ => None,
+    })
+}
+
+fn values_of(values: TroveValues, trove: &Trove) -> BTreeSet<String> {
+    let commands = trove.commands.iter();
+    match values {
+        TroveValues::Names => commands.map(|c| c.name.clone()).collect(),
+        TroveValues::References => commands
+            .map(|c| format!("{}/{}", c.namespace, c.name))
+            .chain(trove.namespaces().into_iter().map(String::from))
+            .collect(),
+        TroveValues::Namespaces => trove.namespaces().into_iter().map(String::from).collect(),
+        TroveValues::Tags => commands.flat_map(|c| c.tags.iter().cloned()).collect(),
+    }
+}
+
+/// Values for `arg`, from the trove or the values clap knows, like the `Mode` of `hoard sync`
+fn complete_arg(path: &[&str], arg: &Arg, trove: &Trove) -> Option<BTreeSet<String>> {
+    if let Some(values) = trove_values(path, arg) {
+        return Some(values_of(values, trove));
+    }
+    let possible_values: BTreeSet<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect();
+    (!possible_values.is_empty()).then_some(possible_values)
+}
+
+fn find_option<'a>(command: &'a Command, word: &str) -> Option<&'a Arg> {
+    if let Some(long) = word.strip_prefix("--") {
+        command.get_arguments().find(|a| a.get_long() == Some(long))
+    } else {
+        let mut short = word.strip_prefix('-')?.chars();
+        let flag = short.next()?;
+        // `-nvalue` already contains its value
+        if short.next().is_some() {
+            return None;
+        }
+        command
+            .get_arguments()
+            .find(|a| a.get_short() == Some(flag))
+    }
+}
+
+/// Values to complete for the last of `words`, the arguments typed after `hoard`.
+///
+/// Returns `None` if the word is not the value of an argument hoard knows values for,
+/// like subcommands, flags or free text. The completion script generated by clap handles those
+pub fn complete(cli: &Command, words: &[String], trove: &Trove) -> Option<Vec<String>> {
+    let (current, typed) = words.split_last()?;
+    let mut command = cli;
+    let mut path: Vec<&str> = Vec::new();
+    let mut positional = 0;
+    let mut option: Option<&Arg> = None;
+    for word in typed {
+        if option.take().is_some() {
+            continue;
+        }
+        if word.starts_with('-') && word.len() > 1 {
+            if !word.contains('=') {
+                option = find_option(command, word).filter(|a| a.get_action().takes_values());
+            }
+        } else if let Some(subcommand) = command.find_subcommand(word) {
+            command = subcommand;
+            path.push(subcommand.get_name());
+            positional = 0;
+        } else {
+            positional += 1;
+        }
+    }
+
+    let arg = match option {
+        Some(option) => option,
+        None if current.starts_with('-') || command.has_subcommands() => return None,
+        None => {
+            let mut positionals: Vec<&Arg> = command.get_positionals().collect();
+            positionals.sort_by_key(|a| a.get_index());
+            // Arguments taking many values, like the commands of `hoard mv`, take all remaining words
+            let last = positionals.len().checked_sub(1)?;
+            positionals[positional.min(last)]
+        }
+    };
+    let values = complete_arg(&path, arg, trove)?;
+    Some(
+        values
+            .into_iter()
+            .filter(|value| value.starts_with(current.as_str()))
+            .collect(),
+    )
+}
+
+/// Asks `hoard completions --complete` for values from the trove and falls back to the generated completion
+const BASH_DYNAMIC: &str = r#"
+_hoard_trove() {
+    local values
+    if values="$(hoard completions --complete -- "${COMP_WORDS[@]:1:COMP_CWORD}" 2>/dev/null)"; then
+        local IFS=$'\n'
+        COMPREPLY=($values)
+        return 0
+    fi
+    _hoard "$@"
+}
+
+complete -F _hoard_trove -o bashdefault -o default hoard
+"#;
+
+const ZSH_DYNAMIC: &str = r#"
+_hoard_trove() {
+    local values
+    if values="$(hoard completions --complete -- "${(@)words[2,$CURRENT]}" 2>/dev/null)"; then
+        local -a candidates
+        candidates=(${(f)values})
+        compadd -a candidates
+        return 0
+    fi
+    _hoard "$@"
+}
+
+compdef _hoard_trove hoard
+"#;
+
+const FISH_DYNAMIC: &str = r"
+function __hoard_trove_values
+    set -l words (commandline -opc) (commandline -ct)
+    set -e words[1]
+    hoard completions --complete -- $words 2>/dev/null
+end
+
+complete -c hoard -f -n '__hoard_trove_values >/dev/null' -a '(__hoard_trove_values)'
+";
+
+/// Completion script for `shell`: everything clap knows about the arguments,
+/// and values from the trove for command names, namespaces and tags
+pub fn script(cli: &mut Command, shell: Shell) -> String {
+    let (completion_shell, dynamic) = match shell {
+        Shell::Bash => (CompletionShell::Bash, BASH_DYNAMIC),
+        Shell::Zsh => (CompletionShell::Zsh, ZSH_DYNAMIC),
+        Shell::Fish => (CompletionShell::Fish, FISH_DYNAMIC),
+    };
+    let mut script = Vec::new();
+    clap_complete::generate(completion_shell, cli, "hoard", &mut script);
+    let mut script = String::from_utf8(script).expect("completion scripts are UTF-8");
+    script.push_str(dynamic);
+    script
+}
+
+#[cfg(test)]
+mod test_completions {
+    use super::*;
+    use crate::cli_commands::Cli;
+    use crate::core::HoardCmd;
+    use clap::CommandFactory;
+
+    fn trove() -> Trove {
+        Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("status")
+                .with_namespace("git")
+                .with_command("git status")
+                .with_tags_raw("vcs"),
+            HoardCmd::default()
+                .with_name("ps")
+                .with_namespace("docker")
+                .with_command("docker ps")
+                .with_tags_raw("container,vcs"),
+        ])
+    }
+
+    fn complete_words(line: &str) -> Option<Vec<String>> {
+        let mut cli = Cli::command();
+        cli.build();
+        let mut words: Vec<String> = line.split(' ').map(String::from).collect();
+        words.remove(0);
+        complete(&cli, &words, &trove())
+    }
+
+    #[test]
+    fn values_from_the_trove() {
+        assert_eq!(
+            Some(vec!["ps".into(), "status".into()]),
+            complete_words("hoard pick -n ")
+        );
+        assert_eq!(
+            Some(vec!["status".into()]),
+            complete_words("hoard show --name s")
+        );
+        assert_eq!(
+            Some(vec!["git".into()]),
+            complete_words("hoard show -n status --namespace g")
+        );
+        assert_eq!(
+            Some(vec!["container".into(), "vcs".into()]),
+            complete_words("hoard tag rename ")
+        );
+        assert_eq!(
+            Some(vec!["docker".into()]),
+            complete_words("hoard namespace merge git d")
+        );
+        assert_eq!(
+            Some(vec!["git".into(), "git/status".into()]),
+            complete_words("hoard mv docker/ps g")
+        );
+        assert_eq!(
+            Some(vec!["vcs".into()]),
+            complete_words("hoard export -t v")
+        );
+    }
+
+    #[test]
+    fn values_known_to_clap() {
+        assert_eq!(
+            Some(vec!["login".into(), "logout".into()]),
+            complete_words("hoard sync lo")
+        );
+        assert_eq!(
+            Some(vec!["zsh".into()]),
+            complete_words("hoard completions z")
+        );
+    }
+
+    #[test]
+    fn leave_the_rest_to_the_shell() {
+        assert_eq!(None, complete_words("hoard p"));
+        assert_eq!(None, complete_words("hoard pick --n"));
+        assert_eq!(None, complete_words("hoard new -c "));
+        assert_eq!(None, complete_words("hoard tag "));
+    }
+
+    #[test]
+    fn named_arguments_exist() {
+        let cli = Cli::command();
+        for (path, id, _) in NAMED_ARGUMENTS {
+            let command = path.iter().fold(&cli, |command, name| {
+                command.find_subcommand(name).expect("subcommand exists")
+            });
+            assert!(
+                command.get_arguments().any(|a| a.get_id() == id),
+                "{path:?} {id}"
+            );
+        }
+    }
+
+    #[test]
+    fn scripts_for_all_shells() {
+        for shell in [Shell::Bash, Shell::Zsh, Shell::Fish] {
+            let script = script(&mut Cli::command(), shell);
+            assert!(script.contains("remove-namespace"));
+            assert!(script.contains("hoard completions --complete --"));
+        }
+    }
+}
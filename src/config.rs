@@ -1,9 +1,12 @@
+use crate::core::ranking::Ranking;
 use crate::gui::prompts::prompt_input;
+use crate::subscriptions::Subscription;
 use anyhow::{anyhow, Error, Result};
 use log::info;
 use serde::{Deserialize, Serialize};
 use std::{
     fs,
+    io::{self, IsTerminal},
     path::{Path, PathBuf},
 };
 
@@ -34,6 +37,13 @@ pub struct HoardConfig {
     pub sync_server_url: Option<String>,
     pub api_token: Option<String>,
     pub gpt_api_key: Option<String>,
+    // Troves to keep up to date, read-only and separate from your own commands
+    pub subscriptions: Option<Vec<Subscription>>,
+    // Order of listed commands: usage, frecency, recent or alphabetical
+    pub ranking: Option<Ranking>,
+    // `trove_path` as in config.yml while another trove file is used, like one given with `--trove`
+    #[serde(skip)]
+    pub saved_trove_path: Option<PathBuf>,
 }
 
 impl Default for HoardConfig {
@@ -54,6 +64,9 @@ impl Default for HoardConfig {
             sync_server_url: Some(Self::default_sync_server_url()),
             api_token: None,
             gpt_api_key: None,
+            subscriptions: None,
+            ranking: None,
+            saved_trove_path: None,
         }
     }
 }
@@ -76,6 +89,9 @@ impl HoardConfig {
             sync_server_url: Some(Self::default_sync_server_url()),
             api_token: None,
             gpt_api_key: None,
+            subscriptions: None,
+            ranking: None,
+            saved_trove_path: None,
         }
     }
 
@@ -101,9 +117,20 @@ impl HoardConfig {
             sync_server_url: self.sync_server_url,
             api_token: self.api_token,
             gpt_api_key: self.gpt_api_key,
+            subscriptions: self.subscriptions,
+            ranking: self.ranking,
+            saved_trove_path: self.saved_trove_path,
         }
     }
 
+    /// Use the trove file at `trove_path` instead of the configured one, without saving it to config.yml
+    fn use_trove_path(&mut self, trove_path: PathBuf) {
+        if self.saved_trove_path.is_none() {
+            self.saved_trove_path = self.trove_path.take();
+        }
+        self.trove_path = Some(trove_path);
+    }
+
     fn default_parameter_token() -> String {
         "#".to_string()
     }
@@ -130,37 +157,44 @@ impl HoardConfig {
     }
 }
 
-/// Loads hoard config file at $HOME/.hoard/config.yml.
-/// if `hoard_home_path` is set, try to read it from that custom path
+/// Loads hoard config file config.yml in `hoard_dir`, or in $HOME/.config/hoard if `hoard_dir` is not set.
+/// If there is no config.yml yet, a new one will be created there
 ///
-/// If no `hoard_home_path` is found, a new config.yml will be created at the specified path
+/// The trove file is, first match wins:
+/// - `trove_path`, from `--trove` or `$HOARD_TROVE`
+/// - trove.yml in the current directory, if `read_from_current_directory` is set
+/// - `trove_path` of config.yml
 #[allow(clippy::module_name_repetitions)]
-pub fn load_or_build_config(hoard_home_path: Option<String>) -> Result<HoardConfig> {
+pub fn load_or_build_config(
+    hoard_dir: Option<PathBuf>,
+    trove_path: Option<PathBuf>,
+) -> Result<HoardConfig> {
     // First check if custom path should be used
-    hoard_home_path.map_or_else(load_or_build_default_path, |custom_path| {
-        info!("Found custom_path {:?}", custom_path);
-        let path = PathBuf::from(custom_path);
-        load_or_build(&path)
-    })
-}
-
-fn load_or_build_default_path() -> Result<HoardConfig, Error> {
-    dirs::home_dir().map_or_else(
-        || Err(anyhow!("No $HOME directory found for hoard config")),
-        |home| load_or_build(&home),
-    )
+    let hoard_dir = match hoard_dir {
+        Some(custom_path) => {
+            info!("Found custom_path {:?}", custom_path);
+            custom_path
+        }
+        None => dirs::home_dir()
+            .ok_or_else(|| anyhow!("No $HOME directory found for hoard config"))?
+            .join(HOARD_HOMEDIR),
+    };
+    let mut config = load_or_build(&hoard_dir)?;
+    if let Some(trove_path) = trove_path {
+        info!("Using trove file {:?}", trove_path);
+        config.use_trove_path(trove_path);
+    }
+    Ok(config)
 }
 
 #[allow(clippy::useless_let_if_seq)]
-fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
-    info!("Loading or building in {:?}", path);
-    let home_path = Path::new(&path);
+fn load_or_build(hoard_dir: &Path) -> Result<HoardConfig, Error> {
+    info!("Loading or building in {:?}", hoard_dir);
 
-    // Check if $HOME/.hoard directory exists. Create it if it does not exist
-    let hoard_dir = home_path.join(HOARD_HOMEDIR);
+    // Check if the hoard directory exists. Create it if it does not exist
     if !hoard_dir.exists() {
         info!("Creating {:?}", hoard_dir);
-        fs::create_dir_all(&hoard_dir)?;
+        fs::create_dir_all(hoard_dir)?;
     }
 
     let hoard_config_path = hoard_dir.join(HOARD_CONFIG);
@@ -171,15 +205,13 @@ fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
         let f = std::fs::File::open(&hoard_config_path)?;
         let mut loaded_config: HoardConfig = serde_yaml::from_reader::<_, HoardConfig>(f)?;
 
-        append_missing_default_values_to_config(
-            &mut loaded_config,
-            &hoard_dir,
-            &hoard_config_path,
-        )?;
+        append_missing_default_values_to_config(&mut loaded_config, hoard_dir, &hoard_config_path)?;
+        // The directory may have moved, like a config mounted into a container
+        loaded_config.config_home_path = Some(hoard_dir.to_path_buf());
 
         let path_buf = Path::new(HOARD_FILE).to_path_buf();
         if loaded_config.read_from_current_directory.unwrap() && path_buf.exists() {
-            loaded_config.trove_path = Some(path_buf);
+            loaded_config.use_trove_path(path_buf);
         }
         // Sanity check. If the config makes sense
         assert!(loaded_config.parameter_token != loaded_config.parameter_ending_token, "Your parameter token {} is equal to your ending token {}. Please set one of them to another character!", loaded_config.parameter_token.as_ref().unwrap(), loaded_config.parameter_ending_token.as_ref().unwrap());
@@ -187,7 +219,12 @@ fn load_or_build(path: &Path) -> Result<HoardConfig, Error> {
         Ok(loaded_config)
     } else {
         info!("Config file does not exist. Creating new one");
-        let new_config = HoardConfig::new(&hoard_dir).with_default_namespace();
+        // Without a terminal to ask, like in scripts or containers, the default namespace stays `default`
+        let new_config = if io::stdin().is_terminal() {
+            HoardConfig::new(hoard_dir).with_default_namespace()
+        } else {
+            HoardConfig::new(hoard_dir)
+        };
         save_config(&new_config, &hoard_config_path)?;
         Ok(new_config)
     };
@@ -286,6 +323,10 @@ pub struct ClientResponse {
 // }
 
 fn save_config(config_to_save: &HoardConfig, config_path: &Path) -> Result<(), Error> {
+    let mut config_to_save = config_to_save.clone();
+    if let Some(saved_trove_path) = config_to_save.saved_trove_path.take() {
+        config_to_save.trove_path = Some(saved_trove_path);
+    }
     let s = serde_yaml::to_string(&config_to_save)?;
     fs::write(config_path, s).expect("Unable to write config file");
     Ok(())
@@ -298,7 +339,10 @@ pub fn save_hoard_config_file(config_to_save: &HoardConfig, base_path: &Path) ->
 
 #[cfg(test)]
 mod test_config {
-    use super::{save_parameter_token, HoardConfig, HOARD_CONFIG};
+    use super::{
+        load_or_build_config, save_hoard_config_file, save_parameter_token, HoardConfig,
+        HOARD_CONFIG, HOARD_FILE,
+    };
     use std::fs::File;
     use tempfile::tempdir;
 
@@ -317,4 +361,29 @@ mod test_config {
         let parsed_config = serde_yaml::from_reader::<_, HoardConfig>(f).ok().unwrap();
         assert_eq!(parsed_config.parameter_token, Some(String::from("@")));
     }
+
+    #[test]
+    fn trove_path_override_is_not_saved() {
+        let tmp_dir = tempdir().ok().unwrap();
+        let hoard_dir = tmp_dir.path().join("work");
+        let work_trove = tmp_dir.path().join("work.yml");
+        // An existing config, so hoard doesn't ask for a default namespace in a terminal
+        std::fs::create_dir(&hoard_dir).unwrap();
+        let mut config = HoardConfig::new(&hoard_dir);
+        config.read_from_current_directory = Some(false);
+        save_hoard_config_file(&config, &hoard_dir).unwrap();
+
+        let config = load_or_build_config(Some(hoard_dir.clone()), None).unwrap();
+        assert_eq!(Some(hoard_dir.clone()), config.config_home_path);
+        assert_eq!(Some(hoard_dir.join(HOARD_FILE)), config.trove_path);
+
+        let config =
+            load_or_build_config(Some(hoard_dir.clone()), Some(work_trove.clone())).unwrap();
+        assert_eq!(Some(work_trove), config.trove_path);
+        save_hoard_config_file(&config, &hoard_dir).unwrap();
+
+        let f = File::open(hoard_dir.join(HOARD_CONFIG)).ok().unwrap();
+        let parsed_config = serde_yaml::from_reader::<_, HoardConfig>(f).ok().unwrap();
+        assert_eq!(Some(hoard_dir.join(HOARD_FILE)), parsed_config.trove_path);
+    }
 }
@@ -0,0 +1,322 @@
+use anyhow::{anyhow, Result};
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::filter::CommandSelection;
+
+/// What a bulk operation changed on one command
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub namespace: String,
+    pub name: String,
+    pub description: String,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}: {}", self.namespace, self.name, self.description)
+    }
+}
+
+/// Apply `edit` to all commands accepted by `selected` and bump `modified` of the commands it changed.
+/// `edit` describes what it changed, or returns `None` if the command stays as it is
+fn edit_commands<S, E>(trove: &mut Trove, selected: S, mut edit: E) -> Vec<Change>
+where
+    S: Fn(&HoardCmd) -> bool,
+    E: FnMut(&mut HoardCmd) -> Option<String>,
+{
+    let now = SystemTime::now();
+    let mut changes = Vec::new();
+    for command in trove.commands.iter_mut().filter(|c| selected(c)) {
+        let namespace = command.namespace.clone();
+        let name = command.name.clone();
+        if let Some(description) = edit(command) {
+            command.modified = now;
+            changes.push(Change {
+                namespace,
+                name,
+                description,
+            });
+        }
+    }
+    trove.update_namespaces();
+    changes
+}
+
+/// A tag must not be empty or contain whitespaces or `,`
+pub fn check_tag(tag: &str) -> Result<()> {
+    HoardCmd::are_tags_valid(tag)?;
+    if tag.contains(|c: char| c.is_whitespace() || c == ',') {
+        return Err(anyhow!("A tag can't contain whitespaces or ','"));
+    }
+    Ok(())
+}
+
+/// Add `tag` to all selected commands which don't have it yet
+pub fn add_tag(trove: &mut Trove, selection: &CommandSelection, tag: &str) -> Result<Vec<Change>> {
+    check_tag(tag)?;
+    Ok(edit_commands(
+        trove,
+        |c| selection.matches(c) && !c.tags.iter().any(|t| t == tag),
+        |command| {
+            command.tags.push(tag.to_string());
+            Some(format!("add tag [{tag}]"))
+        },
+    ))
+}
+
+/// Remove `tag` from all selected commands
+pub fn remove_tag(trove: &mut Trove, selection: &CommandSelection, tag: &str) -> Vec<Change> {
+    edit_commands(
+        trove,
+        |c| selection.matches(c) && c.tags.iter().any(|t| t == tag),
+        |command| {
+            command.tags.retain(|t| t != tag);
+            Some(format!("remove tag [{tag}]"))
+        },
+    )
+}
+
+/// Rename the tag `old` of all selected commands to `new`. Commands which already have both keep `new` once
+pub fn rename_tag(
+    trove: &mut Trove,
+    selection: &CommandSelection,
+    old: &str,
+    new: &str,
+) -> Result<Vec<Change>> {
+    check_tag(new)?;
+    if old == new {
+        return Ok(Vec::new());
+    }
+    Ok(edit_commands(
+        trove,
+        |c| selection.matches(c) && c.tags.iter().any(|t| t == old),
+        |command| {
+            let has_new = command.tags.iter().any(|t| t == new);
+            command.tags = command
+                .tags
+                .iter()
+                .filter(|t| !has_new || *t != old)
+                .map(|t| if t == old { new.to_string() } else { t.clone() })
+                .collect();
+            Some(format!("rename tag [{old}] to [{new}]"))
+        },
+    ))
+}
+
+/// Find a command by `<namespace>/<name>`, or by its name alone if no other namespace uses it
+pub fn find_command<'a>(trove: &'a Trove, reference: &str) -> Result<&'a HoardCmd> {
+    if let Some(command) = trove.commands.iter().find(|c| {
+        reference
+            .strip_prefix(c.namespace.as_str())
+            .and_then(|name| name.strip_prefix('/'))
+            == Some(c.name.as_str())
+    }) {
+        return Ok(command);
+    }
+    let named: Vec<&HoardCmd> = trove
+        .commands
+        .iter()
+        .filter(|c| c.name == reference)
+        .collect();
+    match named.as_slice() {
+        [command] => Ok(command),
+        [] => Err(anyhow!("Command not found [{reference}]")),
+        _ => {
+            let references: Vec<String> = named
+                .iter()
+                .map(|c| format!("{}/{}", c.namespace, c.name))
+                .collect();
+            Err(anyhow!(
+                "Several commands are named [{reference}], choose one of: {}",
+                references.join(", ")
+            ))
+        }
+    }
+}
+
+/// Move all commands accepted by `selected` into `namespace`.
+/// A command whose name is already taken there is numbered, like `status_2`
+pub fn move_to_namespace<S>(trove: &mut Trove, selected: S, namespace: &str) -> Result<Vec<Change>>
+where
+    S: Fn(&HoardCmd) -> bool,
+{
+    if namespace.trim().is_empty() {
+        return Err(anyhow!("Namespace can't be empty"));
+    }
+    let moved: Vec<usize> = (0..trove.commands.len())
+        .filter(|i| {
+            let command = &trove.commands[*i];
+            command.namespace != namespace && selected(command)
+        })
+        .collect();
+    let now = SystemTime::now();
+    let mut changes = Vec::new();
+    for i in moved {
+        let is_taken = |name: &str| {
+            trove
+                .commands
+                .iter()
+                .any(|c| c.namespace == namespace && c.name == name)
+        };
+        let base_name = &trove.commands[i].name;
+        let mut name = base_name.clone();
+        let mut suffix = 1;
+        while is_taken(&name) {
+            suffix += 1;
+            name = format!("{base_name}_{suffix}");
+        }
+
+        let command = &mut trove.commands[i];
+        let description = if name == command.name {
+            format!("move to [{namespace}]")
+        } else {
+            format!("move to [{namespace}] as [{name}]")
+        };
+        changes.push(Change {
+            namespace: command.namespace.clone(),
+            name: command.name.clone(),
+            description,
+        });
+        command.namespace = namespace.to_string();
+        command.name = name;
+        command.modified = now;
+    }
+    trove.update_namespaces();
+    Ok(changes)
+}
+
+fn check_namespace_exists(trove: &Trove, namespace: &str) -> Result<()> {
+    if trove.namespaces().contains(&namespace) {
+        Ok(())
+    } else {
+        Err(anyhow!("No commands found in namespace [{namespace}]"))
+    }
+}
+
+/// Rename the namespace `old` to `new`, which must not exist yet
+pub fn rename_namespace(trove: &mut Trove, old: &str, new: &str) -> Result<Vec<Change>> {
+    check_namespace_exists(trove, old)?;
+    if trove.namespaces().contains(&new) {
+        return Err(anyhow!(
+            "Namespace [{new}] already exists, use [ hoard namespace merge ] to move commands into it"
+        ));
+    }
+    move_to_namespace(trove, |c| c.namespace == old, new)
+}
+
+/// Move all commands of the namespaces `from` into the namespace `into`, which may exist already
+pub fn merge_namespaces(trove: &mut Trove, from: &[String], into: &str) -> Result<Vec<Change>> {
+    for namespace in from {
+        check_namespace_exists(trove, namespace)?;
+    }
+    move_to_namespace(trove, |c| from.contains(&c.namespace), into)
+}
+
+#[cfg(test)]
+mod test_bulk {
+    use super::*;
+    use crate::query::Query;
+    use std::time::UNIX_EPOCH;
+
+    /// Commands modified long ago, so tests see which ones are changed
+    fn trove() -> Trove {
+        let command = |name: &str, namespace: &str, tags: &str| {
+            let mut command = HoardCmd::default()
+                .with_name(name)
+                .with_namespace(namespace)
+                .with_command(&format!("{namespace} {name}"))
+                .with_tags_raw(tags);
+            command.modified = UNIX_EPOCH;
+            command
+        };
+        Trove::from_commands(&[
+            command("status", "git", "vcs"),
+            command("push", "git", "vcs,remote"),
+            command("status", "jj", "vcs,new"),
+            command("ps", "docker", "remote"),
+        ])
+    }
+
+    fn references(trove: &Trove) -> Vec<String> {
+        trove
+            .commands
+            .iter()
+            .map(|c| format!("{}/{}", c.namespace, c.name))
+            .collect()
+    }
+
+    #[test]
+    fn add_and_remove_tags() {
+        let mut trove = trove();
+        let selection = CommandSelection {
+            query: Some(Query::parse("ns:git").unwrap()),
+            ..CommandSelection::default()
+        };
+        let added = add_tag(&mut trove, &selection, "remote").unwrap();
+        assert_eq!(1, added.len());
+        assert_eq!("git/status: add tag [remote]", added[0].to_string());
+        assert_ne!(UNIX_EPOCH, trove.commands[0].modified);
+        assert_eq!(UNIX_EPOCH, trove.commands[2].modified);
+        assert!(add_tag(&mut trove, &selection, "two words").is_err());
+
+        let removed = remove_tag(&mut trove, &CommandSelection::default(), "remote");
+        assert_eq!(3, removed.len());
+        assert!(trove
+            .commands
+            .iter()
+            .all(|c| !c.tags.contains(&"remote".to_string())));
+    }
+
+    #[test]
+    fn rename_tags() {
+        let mut trove = trove();
+        let changes = rename_tag(&mut trove, &CommandSelection::default(), "new", "vcs").unwrap();
+        assert_eq!(1, changes.len());
+        assert_eq!(vec!["vcs"], trove.commands[2].tags);
+
+        rename_tag(&mut trove, &CommandSelection::default(), "vcs", "scm").unwrap();
+        assert_eq!(vec!["scm", "remote"], trove.commands[1].tags);
+    }
+
+    #[test]
+    fn find_commands_by_reference() {
+        let trove = trove();
+        assert_eq!("jj", find_command(&trove, "jj/status").unwrap().namespace);
+        assert_eq!("git", find_command(&trove, "push").unwrap().namespace);
+        assert!(find_command(&trove, "status").is_err());
+        assert!(find_command(&trove, "git/ps").is_err());
+    }
+
+    #[test]
+    fn move_commands_with_numbered_names() {
+        let mut trove = trove();
+        let changes = move_to_namespace(&mut trove, |c| c.namespace == "jj", "git").unwrap();
+        assert_eq!(
+            "jj/status: move to [git] as [status_2]",
+            changes[0].to_string()
+        );
+        assert_eq!(vec!["docker", "git"], trove.namespaces());
+        assert!(trove.namespaces.contains("git"));
+        assert!(!trove.namespaces.contains("jj"));
+    }
+
+    #[test]
+    fn rename_and_merge_namespaces() {
+        let mut trove = trove();
+        assert!(rename_namespace(&mut trove, "jj", "git").is_err());
+        assert!(rename_namespace(&mut trove, "hg", "vcs").is_err());
+        assert_eq!(2, rename_namespace(&mut trove, "git", "vcs").unwrap().len());
+        assert_eq!(vec!["docker", "jj", "vcs"], trove.namespaces());
+
+        let from = vec![String::from("jj"), String::from("docker")];
+        merge_namespaces(&mut trove, &from, "vcs").unwrap();
+        assert_eq!(
+            vec!["vcs/status", "vcs/push", "vcs/status_2", "vcs/ps"],
+            references(&trove)
+        );
+        assert_eq!(vec!["vcs"], trove.namespaces());
+    }
+}
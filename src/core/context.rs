@@ -0,0 +1,111 @@
+use std::env;
+use std::path::{Path, PathBuf};
+
+use crate::core::HoardCmd;
+
+/// Number of directories and repositories remembered per command
+pub const MAX_USAGE_CONTEXTS: usize = 20;
+
+/// Where a command is used: the working directory, and the root of the git repository it is part of
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsageContext {
+    pub directory: PathBuf,
+    pub repository: Option<PathBuf>,
+}
+
+impl UsageContext {
+    /// Context of the current working directory, if it can be read
+    pub fn current() -> Option<Self> {
+        env::current_dir().ok().map(Self::of)
+    }
+
+    /// Context of `directory`. The repository root is the closest parent directory containing `.git`
+    pub fn of(directory: PathBuf) -> Self {
+        let repository = directory
+            .ancestors()
+            .find(|d| d.join(".git").exists())
+            .map(Path::to_path_buf);
+        Self {
+            directory,
+            repository,
+        }
+    }
+
+    /// `true` if `command` was used in this directory before
+    pub fn is_used_here(&self, command: &HoardCmd) -> bool {
+        command.used_in_directories.contains(&self.directory)
+    }
+
+    /// `true` if `command` was used anywhere in this git repository before
+    pub fn is_used_in_repository(&self, command: &HoardCmd) -> bool {
+        self.repository
+            .as_ref()
+            .is_some_and(|repository| command.used_in_repositories.contains(repository))
+    }
+
+    /// How closely `command` relates to this context. Commands used in this directory rank highest,
+    /// then commands used in this repository
+    pub fn relevance(&self, command: &HoardCmd) -> u8 {
+        if self.is_used_here(command) {
+            2
+        } else {
+            u8::from(self.is_used_in_repository(command))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_context {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn find_repository_root() {
+        let dir = tempdir().unwrap();
+        let repository = dir.path().join("project");
+        let nested = repository.join("src/core");
+        fs::create_dir_all(&nested).unwrap();
+        fs::create_dir(repository.join(".git")).unwrap();
+
+        let context = UsageContext::of(nested.clone());
+        assert_eq!(nested, context.directory);
+        assert_eq!(Some(repository), context.repository);
+        assert_eq!(None, UsageContext::of(dir.path().to_path_buf()).repository);
+    }
+
+    #[test]
+    fn relevance_of_commands() {
+        let context = UsageContext {
+            directory: PathBuf::from("/work/project/src"),
+            repository: Some(PathBuf::from("/work/project")),
+        };
+        let mut here = HoardCmd::default();
+        here.mut_record_context(&context);
+        let mut in_repository = HoardCmd::default();
+        in_repository.mut_record_context(&UsageContext {
+            directory: PathBuf::from("/work/project"),
+            ..context.clone()
+        });
+        let elsewhere = HoardCmd::default();
+
+        assert_eq!(2, context.relevance(&here));
+        assert_eq!(1, context.relevance(&in_repository));
+        assert_eq!(0, context.relevance(&elsewhere));
+    }
+
+    #[test]
+    fn remembered_contexts_are_unique_and_capped() {
+        let mut command = HoardCmd::default();
+        for i in 0..MAX_USAGE_CONTEXTS + 5 {
+            command.mut_record_context(&UsageContext::of(PathBuf::from(format!("/dir/{i}"))));
+        }
+        let first = UsageContext::of(PathBuf::from("/dir/10"));
+        command.mut_record_context(&first);
+        assert_eq!(MAX_USAGE_CONTEXTS, command.used_in_directories.len());
+        assert_eq!(Some(&first.directory), command.used_in_directories.last());
+        assert!(!command
+            .used_in_directories
+            .contains(&PathBuf::from("/dir/0")));
+    }
+}
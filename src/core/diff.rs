@@ -0,0 +1,202 @@
+use serde::Serialize;
+use std::fmt;
+
+use crate::core::HoardCmd;
+
+/// A field which differs between a local and an incoming command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub local: String,
+    pub incoming: String,
+}
+
+/// An incoming command and how it differs from the local command with the same name and namespace
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandChange {
+    pub namespace: String,
+    pub name: String,
+    pub changes: Vec<FieldChange>,
+}
+
+impl CommandChange {
+    /// Compare the fields of `local` and `incoming` a user can edit: command, description and tags
+    pub fn between(local: &HoardCmd, incoming: &HoardCmd) -> Self {
+        let fields = [
+            ("command", local.command.clone(), incoming.command.clone()),
+            (
+                "description",
+                local.description.clone(),
+                incoming.description.clone(),
+            ),
+            (
+                "tags",
+                local.get_tags_as_string(),
+                incoming.get_tags_as_string(),
+            ),
+        ];
+        Self {
+            namespace: incoming.namespace.clone(),
+            name: incoming.name.clone(),
+            changes: fields
+                .into_iter()
+                .filter(|(_, local, incoming)| local != incoming)
+                .map(|(field, local, incoming)| FieldChange {
+                    field,
+                    local,
+                    incoming,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An incoming command without a local counterpart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CommandSummary {
+    pub namespace: String,
+    pub name: String,
+    pub command: String,
+}
+
+impl From<&HoardCmd> for CommandSummary {
+    fn from(command: &HoardCmd) -> Self {
+        Self {
+            namespace: command.namespace.clone(),
+            name: command.name.clone(),
+            command: command.command.clone(),
+        }
+    }
+}
+
+/// What merging a trove into the local one would change
+///
+/// - `added`: Commands without a local command of the same name and namespace
+/// - `replaced`: Commands replacing a local command
+/// - `renamed`: Commands added under a new name next to the local command
+/// - `kept`: Commands ignored in favor of the local command
+/// - `conflicts`: Commands for which the user would be asked what to do
+/// - `identical`: Commands which are already present and are skipped
+#[derive(Debug, Default, Serialize)]
+pub struct MergeDiff {
+    pub added: Vec<CommandSummary>,
+    pub replaced: Vec<CommandChange>,
+    pub renamed: Vec<CommandChange>,
+    pub kept: Vec<CommandChange>,
+    pub conflicts: Vec<CommandChange>,
+    pub identical: Vec<CommandSummary>,
+}
+
+impl MergeDiff {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+fn write_changes(f: &mut fmt::Formatter, title: &str, changes: &[CommandChange]) -> fmt::Result {
+    if changes.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{title} ({}):", changes.len())?;
+    for change in changes {
+        writeln!(f, "  ~ {}/{}", change.namespace, change.name)?;
+        for field in &change.changes {
+            writeln!(f, "      {}:", field.field)?;
+            writeln!(f, "        - {}", field.local)?;
+            writeln!(f, "        + {}", field.incoming)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_summaries(
+    f: &mut fmt::Formatter,
+    title: &str,
+    prefix: &str,
+    commands: &[CommandSummary],
+) -> fmt::Result {
+    if commands.is_empty() {
+        return Ok(());
+    }
+    writeln!(f, "{title} ({}):", commands.len())?;
+    for command in commands {
+        writeln!(
+            f,
+            "  {prefix} {}/{}: {}",
+            command.namespace, command.name, command.command
+        )?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for MergeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_summaries(f, "Added", "+", &self.added)?;
+        write_changes(f, "Replaced", &self.replaced)?;
+        write_changes(f, "Added with a new name", &self.renamed)?;
+        write_changes(f, "Kept local command", &self.kept)?;
+        write_changes(f, "Will ask", &self.conflicts)?;
+        write_summaries(f, "Identical, skipped", "=", &self.identical)?;
+        write!(
+            f,
+            "{} added, {} replaced, {} renamed, {} kept, {} to ask, {} identical",
+            self.added.len(),
+            self.replaced.len(),
+            self.renamed.len(),
+            self.kept.len(),
+            self.conflicts.len(),
+            self.identical.len()
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_diff {
+    use super::*;
+
+    #[test]
+    fn changed_fields() {
+        let local = HoardCmd::default()
+            .with_name("log")
+            .with_namespace("git")
+            .with_command("git log")
+            .with_description("Show log")
+            .with_tags_raw("vcs");
+        let incoming = local
+            .clone()
+            .with_command("git log --oneline")
+            .with_tags_raw("vcs,history");
+        let change = CommandChange::between(&local, &incoming);
+        assert_eq!(
+            vec![
+                FieldChange {
+                    field: "command",
+                    local: "git log".to_string(),
+                    incoming: "git log --oneline".to_string(),
+                },
+                FieldChange {
+                    field: "tags",
+                    local: "vcs".to_string(),
+                    incoming: "vcs,history".to_string(),
+                },
+            ],
+            change.changes
+        );
+    }
+
+    #[test]
+    fn summary_line() {
+        let diff = MergeDiff {
+            added: vec![CommandSummary {
+                namespace: "git".to_string(),
+                name: "status".to_string(),
+                command: "git status".to_string(),
+            }],
+            ..MergeDiff::default()
+        };
+        assert_eq!(
+            "Added (1):\n  + git/status: git status\n1 added, 0 replaced, 0 renamed, 0 kept, 0 to ask, 0 identical",
+            diff.to_string()
+        );
+    }
+}
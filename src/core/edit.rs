@@ -0,0 +1,436 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::SystemTime;
+
+use crate::core::bulk::{check_tag, Change};
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+
+/// Explains the document opened in the editor. Lines starting with `#` are ignored
+const HEADER: &str = "\
+# Edit the commands, then save and close the file to apply the changes.
+# Delete an entry to remove its command, add an entry without `id` to hoard a new command.
+# `id` is the command before editing, leave it as it is. Close without saving to abort.
+";
+
+/// Marks the comments describing why the document is invalid, they are removed before the next try
+const ERROR_COMMENT: &str = "# ERROR: ";
+
+/// A command as it is edited in the YAML document
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EditableCommand {
+    /// `<namespace>/<name>` of the command before editing, missing for new commands
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub name: String,
+    pub namespace: String,
+    pub command: String,
+    pub description: String,
+    pub tags: Vec<String>,
+}
+
+impl From<&HoardCmd> for EditableCommand {
+    fn from(command: &HoardCmd) -> Self {
+        Self {
+            id: Some(reference(command)),
+            name: command.name.clone(),
+            namespace: command.namespace.clone(),
+            command: command.command.clone(),
+            description: command.description.clone(),
+            tags: command.tags.clone(),
+        }
+    }
+}
+
+fn reference(command: &HoardCmd) -> String {
+    format!("{}/{}", command.namespace, command.name)
+}
+
+/// Why an edited document can't be applied
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EditError {
+    /// Line of the document the error is about, starting at 1. `None` for the whole document
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for EditError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {line}: {}", self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+/// What applying an edited document does to the trove
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Edits {
+    pub added: Vec<EditableCommand>,
+    /// Edited commands by the `id` they had before
+    pub updated: Vec<EditableCommand>,
+    /// `<namespace>/<name>` of the removed commands
+    pub removed: Vec<String>,
+}
+
+/// The YAML document to edit `commands` in
+pub fn to_document(commands: &[&HoardCmd]) -> String {
+    let commands: Vec<EditableCommand> =
+        commands.iter().map(|c| EditableCommand::from(*c)).collect();
+    let yaml = serde_yaml::to_string(&commands).expect("commands can always be written as YAML");
+    format!("{HEADER}{}", yaml.trim_start_matches("---\n"))
+}
+
+/// `document` without the error comments added by [`with_error_comments`]
+fn without_error_comments(document: &str) -> String {
+    let mut without = String::new();
+    for line in document
+        .lines()
+        .filter(|line| !line.starts_with(ERROR_COMMENT))
+    {
+        without.push_str(line);
+        without.push('\n');
+    }
+    without
+}
+
+/// `document` with each error as comment above the line it is about
+pub fn with_error_comments(document: &str, errors: &[EditError]) -> String {
+    let document = without_error_comments(document);
+    let mut annotated = String::new();
+    let comment = |error: &EditError| format!("{ERROR_COMMENT}{}\n", error.message);
+    for error in errors.iter().filter(|e| e.line.is_none()) {
+        annotated.push_str(&comment(error));
+    }
+    for (i, line) in document.lines().enumerate() {
+        for error in errors.iter().filter(|e| e.line == Some(i + 1)) {
+            annotated.push_str(&comment(error));
+        }
+        annotated.push_str(line);
+        annotated.push('\n');
+    }
+    let line_count = document.lines().count();
+    for error in errors
+        .iter()
+        .filter(|e| e.line.is_some_and(|l| l > line_count))
+    {
+        annotated.push_str(&comment(error));
+    }
+    annotated
+}
+
+/// Lines the entries of the document start at, like `- id: git/status`
+fn entry_lines(document: &str) -> Vec<usize> {
+    document
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| line.starts_with("- ") || *line == "-")
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+fn check_entry(entry: &EditableCommand) -> Vec<String> {
+    let mut messages = Vec::new();
+    if let Err(e) = HoardCmd::is_name_valid(&entry.name) {
+        messages.push(e.to_string());
+    }
+    if entry.namespace.trim().is_empty() {
+        messages.push(String::from("Namespace can't be empty"));
+    } else if entry.namespace.contains(char::is_whitespace) {
+        messages.push(String::from("Namespace can't contain whitespaces"));
+    }
+    if let Err(e) = HoardCmd::is_command_valid(entry.command.trim()) {
+        messages.push(e.to_string());
+    }
+    for tag in &entry.tags {
+        if let Err(e) = check_tag(tag) {
+            messages.push(format!("{e}: [{tag}]"));
+        }
+    }
+    messages
+}
+
+/// Compare the edited `document` with the `selected` commands of `trove` it was created from.
+///
+/// Returns all errors if an entry is invalid, uses an unknown or repeated `id`,
+/// or would have the same namespace and name as another command
+pub fn edits(
+    trove: &Trove,
+    selected: &[&HoardCmd],
+    document: &str,
+) -> Result<Edits, Vec<EditError>> {
+    let document = without_error_comments(document);
+    let entries: Vec<EditableCommand> =
+        match serde_yaml::from_str::<Option<Vec<EditableCommand>>>(&document) {
+            Ok(entries) => entries.unwrap_or_default(),
+            Err(e) => {
+                return Err(vec![EditError {
+                    line: e.location().map(|location| location.line()),
+                    message: e.to_string(),
+                }])
+            }
+        };
+    let selected_ids: Vec<String> = selected.iter().map(|c| reference(c)).collect();
+    let lines = entry_lines(&document);
+    let mut errors = Vec::new();
+    let mut ids: HashSet<&str> = HashSet::new();
+    let mut references: HashSet<String> = HashSet::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let mut messages = check_entry(entry);
+        if let Some(id) = &entry.id {
+            if !selected_ids.contains(id) {
+                messages.push(format!(
+                    "Unknown id [{id}], remove it to hoard a new command"
+                ));
+            } else if !ids.insert(id) {
+                messages.push(format!("The id [{id}] is used by another entry"));
+            }
+        }
+        let new_reference = format!("{}/{}", entry.namespace, entry.name);
+        let is_taken_in_trove = trove
+            .commands
+            .iter()
+            .any(|c| reference(c) == new_reference && !selected_ids.contains(&new_reference));
+        if !references.insert(new_reference.clone()) || is_taken_in_trove {
+            messages.push(format!(
+                "A command named [{}] already exists in namespace [{}]",
+                entry.name, entry.namespace
+            ));
+        }
+        let line = lines.get(i).copied();
+        errors.extend(
+            messages
+                .into_iter()
+                .map(|message| EditError { line, message }),
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    let mut edits = Edits {
+        removed: selected_ids
+            .iter()
+            .filter(|id| !ids.contains(id.as_str()))
+            .cloned()
+            .collect(),
+        ..Edits::default()
+    };
+    for entry in entries {
+        match &entry.id {
+            None => edits.added.push(entry),
+            Some(id) => {
+                let before = selected
+                    .iter()
+                    .find(|c| reference(c) == *id)
+                    .expect("ids are checked");
+                if EditableCommand::from(*before) != entry {
+                    edits.updated.push(entry);
+                }
+            }
+        }
+    }
+    Ok(edits)
+}
+
+/// Remove, update and add the commands of `edits` in this order, and describe what changed
+pub fn apply(trove: &mut Trove, edits: &Edits) -> Vec<Change> {
+    let now = SystemTime::now();
+    let mut changes = Vec::new();
+    for id in &edits.removed {
+        let Some((namespace, name)) = id.split_once('/') else {
+            continue;
+        };
+        if trove.remove_namespaced_command(namespace, name).is_ok() {
+            changes.push(Change {
+                namespace: namespace.to_string(),
+                name: name.to_string(),
+                description: String::from("removed"),
+            });
+        }
+    }
+    // Find all updated commands before changing any, an update may take the name another one had before
+    let positions: Vec<Option<usize>> = edits
+        .updated
+        .iter()
+        .map(|entry| {
+            trove
+                .commands
+                .iter()
+                .position(|c| Some(reference(c)) == entry.id)
+        })
+        .collect();
+    for (entry, position) in edits.updated.iter().zip(positions) {
+        let Some(command) = position.map(|i| &mut trove.commands[i]) else {
+            continue;
+        };
+        changes.push(Change {
+            namespace: command.namespace.clone(),
+            name: command.name.clone(),
+            description: if reference(command) == format!("{}/{}", entry.namespace, entry.name) {
+                String::from("updated")
+            } else {
+                format!("updated as [{}/{}]", entry.namespace, entry.name)
+            },
+        });
+        command.name.clone_from(&entry.name);
+        command.namespace.clone_from(&entry.namespace);
+        command.command.clone_from(&entry.command);
+        command.description.clone_from(&entry.description);
+        command.tags.clone_from(&entry.tags);
+        command.modified = now;
+    }
+    for entry in &edits.added {
+        trove.commands.push(
+            HoardCmd::default()
+                .with_name(&entry.name)
+                .with_namespace(&entry.namespace)
+                .with_command(&entry.command)
+                .with_description(&entry.description)
+                .with_tags(entry.tags.clone()),
+        );
+        changes.push(Change {
+            namespace: entry.namespace.clone(),
+            name: entry.name.clone(),
+            description: String::from("added"),
+        });
+    }
+    trove.update_namespaces();
+    changes
+}
+
+#[cfg(test)]
+mod test_edit {
+    use super::*;
+
+    fn trove() -> Trove {
+        Trove::from_commands(&[
+            HoardCmd::default()
+                .with_name("status")
+                .with_namespace("git")
+                .with_command("git status")
+                .with_tags_raw("vcs"),
+            HoardCmd::default()
+                .with_name("push")
+                .with_namespace("git")
+                .with_command("git push"),
+            HoardCmd::default()
+                .with_name("ps")
+                .with_namespace("docker")
+                .with_command("docker ps"),
+        ])
+    }
+
+    fn git(trove: &Trove) -> Vec<&HoardCmd> {
+        trove
+            .commands
+            .iter()
+            .filter(|c| c.namespace == "git")
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_document() {
+        let trove = trove();
+        let document = to_document(&git(&trove));
+        assert!(document.starts_with(HEADER));
+        assert!(document.contains("- id: git/status\n"));
+        assert_eq!(Ok(Edits::default()), edits(&trove, &git(&trove), &document));
+    }
+
+    #[test]
+    fn add_update_and_remove_commands() {
+        let mut trove = trove();
+        let document = to_document(&git(&trove))
+            .replace("command: git status", "command: git status --short")
+            .replace("name: push", "name: status")
+            .replace(
+                "id: git/status\n  name: status",
+                "id: git/status\n  name: st",
+            )
+            + "- name: log\n  namespace: git\n  command: git log\n";
+        let document = document.replace("- id: git/push\n  name: status\n  namespace: git\n  command: git push\n  description: \"\"\n  tags: []\n", "");
+
+        let edits = edits(&trove, &git(&trove), &document).unwrap();
+        assert_eq!(vec![String::from("git/push")], edits.removed);
+        assert_eq!("st", edits.updated[0].name);
+        assert_eq!("log", edits.added[0].name);
+
+        let changes: Vec<String> = apply(&mut trove, &edits)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            vec![
+                "git/push: removed",
+                "git/status: updated as [git/st]",
+                "git/log: added"
+            ],
+            changes
+        );
+        let references: Vec<String> = trove.commands.iter().map(reference).collect();
+        assert_eq!(vec!["git/st", "docker/ps", "git/log"], references);
+        assert_eq!("git status --short", trove.commands[0].command);
+        assert_eq!(vec!["vcs"], trove.commands[0].tags);
+    }
+
+    #[test]
+    fn swap_names() {
+        let mut trove = trove();
+        let document = to_document(&git(&trove))
+            .replace("name: status", "name: tmp")
+            .replace("name: push", "name: status")
+            .replace("name: tmp", "name: push");
+        let edits = edits(&trove, &git(&trove), &document).unwrap();
+        apply(&mut trove, &edits);
+        assert_eq!("push", trove.commands[0].name);
+        assert_eq!("git status", trove.commands[0].command);
+        assert_eq!("status", trove.commands[1].name);
+        assert_eq!("git push", trove.commands[1].command);
+    }
+
+    #[test]
+    fn errors_as_comments() {
+        let trove = trove();
+        let document = to_document(&git(&trove))
+            .replace("name: push", "name: ps")
+            .replace(
+                "namespace: git\n  command: git push",
+                "namespace: docker\n  command: \"\"",
+            )
+            .replace("- vcs", "- two words");
+        let errors = edits(&trove, &git(&trove), &document).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(|e| e.message.clone()).collect();
+        assert_eq!(
+            vec![
+                "A tag can't contain whitespaces or ',': [two words]",
+                "Command can't be empty",
+                "A command named [ps] already exists in namespace [docker]"
+            ],
+            messages
+        );
+        let annotated = with_error_comments(&document, &errors);
+        assert!(annotated.contains(
+            "# ERROR: Command can't be empty\n# ERROR: A command named [ps] already exists in namespace [docker]\n- id: git/push\n"
+        ));
+        // Old error comments are replaced when the document is checked again
+        let fixed = annotated.replace("two words", "vcs");
+        let annotated =
+            with_error_comments(&fixed, &edits(&trove, &git(&trove), &fixed).unwrap_err());
+        assert_eq!(2, annotated.matches(ERROR_COMMENT).count());
+    }
+
+    #[test]
+    fn invalid_yaml() {
+        let trove = trove();
+        let document = to_document(&git(&trove)).replace("  tags: []", "  tags: [");
+        let errors = edits(&trove, &git(&trove), &document).unwrap_err();
+        assert_eq!(1, errors.len());
+        assert!(errors[0].line.is_some());
+
+        let errors = edits(&trove, &git(&trove), "- nme: typo\n").unwrap_err();
+        assert!(errors[0].message.contains("unknown field `nme`"));
+    }
+}
@@ -1,14 +1,24 @@
+pub mod bulk;
+pub mod context;
+pub mod diff;
+pub mod edit;
 pub mod error;
 pub mod parameters;
+pub mod prune;
+pub mod ranking;
 pub mod trove;
 
+use crate::core::context::{UsageContext, MAX_USAGE_CONTEXTS};
 use crate::core::error::HoardErr;
+use crate::core::ranking::MAX_USAGE_HISTORY;
 use crate::core::trove::Trove;
+use crate::formats::to_command_name;
 use crate::gui::merge::{with_conflict_resolve_prompt, ConflictResolve};
 use crate::gui::prompts::{prompt_input, prompt_input_validate, prompt_select_with_options};
 use rand::distributions::Alphanumeric;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time;
 
 fn default_time() -> time::SystemTime {
@@ -26,11 +36,16 @@ fn default_time() -> time::SystemTime {
 /// - `modified`: The date and time the command was last modified
 /// - `last_used`: The date and time the command was last used
 /// - `usage_count`: The number of times the command has been used
+/// - `usage_history`: When the command was used the last times, in seconds since the UNIX epoch
+/// - `used_in_directories`: The working directories the command was last used in
+/// - `used_in_repositories`: The git repositories the command was last used in
 /// - `is_favorite`: A flag to indicate if the command is a favorite
 /// - `is_hidden`: A flag to indicate if the command is hidden
 /// - `is_deleted`: A flag to indicate if the command is deleted
 /// - `namespace`: The namespace the command belongs to
 /// - `namespace_id`: The id of the namespace the command belongs to
+/// - `is_read_only`: A flag to indicate if the command comes from a subscribed trove. It is never saved
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HoardCmd {
     /// The name of the command by which it is referenced
@@ -61,6 +76,18 @@ pub struct HoardCmd {
     #[serde(default)]
     pub usage_count: usize,
 
+    /// When the command was used the last times, in seconds since the UNIX epoch. Oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub usage_history: Vec<u64>,
+
+    /// The working directories the command was last used in. Most recent last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub used_in_directories: Vec<PathBuf>,
+
+    /// The root directories of the git repositories the command was last used in. Most recent last
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub used_in_repositories: Vec<PathBuf>,
+
     /// A flag to indicate if the command is a favorite
     #[serde(default)]
     pub is_favorite: bool,
@@ -75,6 +102,10 @@ pub struct HoardCmd {
 
     /// The namespace the command belongs to
     pub namespace: String,
+
+    /// A flag to indicate if the command comes from a subscribed trove and can not be changed
+    #[serde(skip)]
+    pub is_read_only: bool,
 }
 
 impl PartialEq for HoardCmd {
@@ -99,10 +130,14 @@ impl HoardCmd {
             modified: time::SystemTime::now(),
             last_used: time::SystemTime::now(),
             usage_count: 0,
+            usage_history: Vec::new(),
+            used_in_directories: Vec::new(),
+            used_in_repositories: Vec::new(),
             is_favorite: false,
             is_hidden: false,
             is_deleted: false,
             namespace: String::new(),
+            is_read_only: false,
         }
     }
 
@@ -138,6 +173,22 @@ impl HoardCmd {
         Self { tags, ..self }
     }
 
+    #[cfg(test)]
+    /// Set how often the command was used and when it was used last
+    pub fn with_usage(self, usage_count: usize, last_used: time::SystemTime) -> Self {
+        Self {
+            last_used,
+            usage_count,
+            ..self
+        }
+    }
+
+    #[cfg(test)]
+    /// Set when the command was created
+    pub fn with_created(self, created: time::SystemTime) -> Self {
+        Self { created, ..self }
+    }
+
     /// Check if a command is valid for saving
     /// A valid command cant be an empty string
     /// Returns a Result with the error if the command is invalid
@@ -396,7 +447,6 @@ impl HoardCmd {
         }
     }
 
-    #[allow(dead_code)]
     /// Resolves a name conflict when a command should be added to a trove file.
     ///
     /// This function takes a command with a conflicting name and a reference to a trove. It prompts the user to resolve the conflict
@@ -469,6 +519,71 @@ impl HoardCmd {
         }
     } 
 
+    /// Fill in the remaining fields without prompting, for `hoard new --yes`.
+    ///
+    /// Without a name, one is derived from the description or the command and numbered if the namespace
+    /// already has a command with that name. A given name must not be taken yet.
+    /// Returns the error of the first invalid field
+    pub fn with_fields(
+        self,
+        name: Option<String>,
+        namespace: String,
+        description: Option<String>,
+        tags: Option<&str>,
+        trove: &Trove,
+    ) -> Result<Self, HoardErr> {
+        Self::is_command_valid(self.command.trim())?;
+        if namespace.trim().is_empty() {
+            return Err(HoardErr::new("Namespace can't be empty"));
+        }
+        let description = description.unwrap_or_default();
+        let is_taken = |name: &str| {
+            trove
+                .commands
+                .iter()
+                .any(|c| c.namespace == namespace && c.name == name)
+        };
+        let name = if let Some(name) = name {
+            Self::is_name_valid(&name)?;
+            if is_taken(&name) {
+                return Err(HoardErr::new(&format!(
+                    "A command named [{name}] already exists in namespace [{namespace}]"
+                )));
+            }
+            name
+        } else {
+            let text = if description.trim().is_empty() {
+                &self.command
+            } else {
+                &description
+            };
+            let base_name = to_command_name(text);
+            let mut name = base_name.clone();
+            let mut suffix = 1;
+            while is_taken(&name) {
+                suffix += 1;
+                name = format!("{base_name}_{suffix}");
+            }
+            name
+        };
+        if let Some(tags) = tags {
+            Self::are_tags_valid(tags.trim())?;
+            if tags
+                .split(',')
+                .any(|tag| tag.trim().contains(char::is_whitespace))
+            {
+                return Err(HoardErr::new("Tags can't contain whitespaces"));
+            }
+        }
+        Ok(Self {
+            name,
+            description,
+            namespace,
+            ..self
+        }
+        .with_tags_raw(tags.unwrap_or_default()))
+    }
+
     pub fn mut_update_last_used(&mut self) {
         self.last_used = time::SystemTime::now();
     }
@@ -479,6 +594,36 @@ impl HoardCmd {
         self
     }
 
+    /// Count a use of the command now, and remember when it happened to rank it by frecency
+    pub fn mut_record_use(&mut self) -> &mut Self {
+        self.mut_increase_usage_count();
+        self.mut_update_last_used();
+        let now = self
+            .last_used
+            .duration_since(time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        self.usage_history.push(now);
+        let excess = self.usage_history.len().saturating_sub(MAX_USAGE_HISTORY);
+        self.usage_history.drain(..excess);
+        self
+    }
+
+    /// Remember that the command was used in `context`, to rank it higher there
+    pub fn mut_record_context(&mut self, context: &UsageContext) -> &mut Self {
+        fn remember(paths: &mut Vec<PathBuf>, path: &PathBuf) {
+            paths.retain(|p| p != path);
+            paths.push(path.clone());
+            let excess = paths.len().saturating_sub(MAX_USAGE_CONTEXTS);
+            paths.drain(..excess);
+        }
+        remember(&mut self.used_in_directories, &context.directory);
+        if let Some(repository) = &context.repository {
+            remember(&mut self.used_in_repositories, repository);
+        }
+        self
+    }
+
     #[allow(dead_code)]
     /// sets the favorite flag of the command
     pub fn mut_set_favorite(&mut self, is_favorite: bool) -> &mut Self {
@@ -514,6 +659,74 @@ pub fn string_to_tags(tags: &str) -> Vec<String> {
 mod test_commands {
     use super::*;
 
+    #[test]
+    fn fields_without_prompts() {
+        let mut trove = Trove::default();
+        let command = HoardCmd::default().with_command("git status");
+        let filled = command
+            .clone()
+            .with_fields(
+                None,
+                String::from("git"),
+                Some(String::from("Show changes")),
+                Some("git, vcs"),
+                &trove,
+            )
+            .unwrap();
+        assert_eq!("show_changes", filled.name);
+        assert_eq!("git", filled.namespace);
+        assert_eq!(vec!["git", "vcs"], filled.tags);
+
+        let _ = trove.add_command(filled, false);
+        let numbered = command
+            .clone()
+            .with_fields(
+                None,
+                String::from("git"),
+                Some(String::from("Show changes")),
+                None,
+                &trove,
+            )
+            .unwrap();
+        assert_eq!("show_changes_2", numbered.name);
+        assert!(command
+            .with_fields(
+                Some(String::from("show_changes")),
+                String::from("git"),
+                None,
+                None,
+                &trove,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn invalid_fields_without_prompts() {
+        let trove = Trove::default();
+        let command = HoardCmd::default().with_command("git status");
+        let error = |name: Option<&str>, tags: Option<&str>| {
+            command
+                .clone()
+                .with_fields(
+                    name.map(String::from),
+                    String::from("git"),
+                    None,
+                    tags,
+                    &trove,
+                )
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            "Name can't contain whitespaces",
+            error(Some("git status"), None)
+        );
+        assert_eq!("Tags can't be empty", error(None, Some(" ")));
+        assert_eq!("Tags can't contain whitespaces", error(None, Some("a b")));
+        let empty = HoardCmd::default().with_fields(None, String::from("git"), None, None, &trove);
+        assert_eq!("Command can't be empty", empty.unwrap_err().to_string());
+    }
+
     #[test]
     fn one_tag_as_string() {
         let command = HoardCmd::default().with_tags_raw("foo");
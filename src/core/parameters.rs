@@ -136,6 +136,59 @@ pub trait Parameterized {
     fn with_input_parameters(&mut self, token: &str, ending_token: &str) -> HoardCmd;
 }
 
+/// A piece of a command string. Either plain text or a named parameter
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandPart {
+    Text(String),
+    Parameter(String),
+}
+
+/// Splits a command string into plain text and named parameters.
+///
+/// A parameter starts with `token`. Its name ends with `ending_token` if it appears before the next
+/// parameter, otherwise the name ends at the first whitespace.
+///
+/// # Example
+///
+/// ```
+/// let parts = split_parameters("echo #greeting! #name", "#", "!");
+/// assert_eq!(parts, vec![
+///     CommandPart::Text("echo ".to_string()),
+///     CommandPart::Parameter("greeting".to_string()),
+///     CommandPart::Text(" ".to_string()),
+///     CommandPart::Parameter("name".to_string()),
+/// ]);
+/// ```
+pub fn split_parameters(command: &str, token: &str, ending_token: &str) -> Vec<CommandPart> {
+    let mut parts = Vec::new();
+    let mut rest = command;
+    while let Some(start) = rest.find(token) {
+        if start > 0 {
+            parts.push(CommandPart::Text(rest[..start].to_string()));
+        }
+        let after_token = &rest[start + token.len()..];
+        let next_token = after_token.find(token).unwrap_or(after_token.len());
+        let (name, consumed) = match after_token.find(ending_token) {
+            Some(end) if !ending_token.is_empty() && end <= next_token => {
+                (&after_token[..end], end + ending_token.len())
+            }
+            _ => {
+                let end = after_token
+                    .find(char::is_whitespace)
+                    .unwrap_or(after_token.len())
+                    .min(next_token);
+                (&after_token[..end], end)
+            }
+        };
+        parts.push(CommandPart::Parameter(name.to_string()));
+        rest = &after_token[consumed..];
+    }
+    if !rest.is_empty() {
+        parts.push(CommandPart::Text(rest.to_string()));
+    }
+    parts
+}
+
 impl Parameterized for HoardCmd {
     fn is_parameterized(&self, token: &str) -> bool {
         self.command.contains(token)
@@ -262,6 +315,28 @@ mod test_commands {
         assert_eq!(expected, command.replace_parameter("#", "!", "replacement"));
     }
 
+    #[test]
+    fn test_split_parameters() {
+        let expected = vec![
+            CommandPart::Text("echo ".to_string()),
+            CommandPart::Parameter("first name".to_string()),
+            CommandPart::Text(" lives in ".to_string()),
+            CommandPart::Parameter("city".to_string()),
+            CommandPart::Text(" ".to_string()),
+            CommandPart::Parameter(String::new()),
+        ];
+        assert_eq!(
+            expected,
+            split_parameters("echo #first name! lives in #city #", "#", "!")
+        );
+    }
+
+    #[test]
+    fn test_split_parameters_without_parameters() {
+        let expected = vec![CommandPart::Text("echo test".to_string())];
+        assert_eq!(expected, split_parameters("echo test", "#", "!"));
+    }
+
     #[test]
     fn test_replace_parameter_with_endtoken_no_spaces() {
         let command = HoardCmd::default().with_command("test1#thisisacommand!test3");
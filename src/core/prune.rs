@@ -0,0 +1,277 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use crate::core::bulk::Change;
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+
+/// Which commands `hoard prune` removes. Commands matching any criterion are pruned
+#[derive(Debug, Default, Clone)]
+pub struct Criteria {
+    /// Commands not used for this long, or never used and created longer ago
+    pub unused_for: Option<Duration>,
+    pub never_used: bool,
+    /// Commands with the same command text as another one, in any namespace
+    pub duplicates: bool,
+    /// Like `duplicates`, but whitespace and the order of flags don't matter
+    pub near_duplicates: bool,
+}
+
+fn local_date(time: SystemTime) -> String {
+    DateTime::<Local>::from(time).format("%Y-%m-%d").to_string()
+}
+
+/// Command text with single spaces and flags sorted, so `ls  -a -l` and `ls -l -a` are the same
+fn normalize(command: &str) -> String {
+    let (mut flags, words): (Vec<&str>, Vec<&str>) = command
+        .split_whitespace()
+        .partition(|word| word.starts_with('-') && word.len() > 1);
+    flags.sort_unstable();
+    words.into_iter().chain(flags).collect::<Vec<_>>().join(" ")
+}
+
+/// Of every group of commands with the same `key`, all but the one to keep, with the one they duplicate.
+/// Favorites are kept first, then the most used, then the oldest command
+fn duplicates<'a, K>(commands: &[&'a HoardCmd], key: K) -> Vec<(&'a HoardCmd, &'a HoardCmd)>
+where
+    K: Fn(&HoardCmd) -> String,
+{
+    let mut groups: HashMap<String, Vec<&HoardCmd>> = HashMap::new();
+    for command in commands {
+        groups.entry(key(command)).or_default().push(command);
+    }
+    let mut duplicates = Vec::new();
+    for mut group in groups.into_values().filter(|group| group.len() > 1) {
+        group.sort_by_key(|c| Reverse((c.is_favorite, c.usage_count, Reverse(c.created))));
+        let (kept, others) = group.split_first().expect("groups have several commands");
+        duplicates.extend(others.iter().map(|other| (*other, *kept)));
+    }
+    duplicates
+}
+
+/// Commands of `trove` matching `criteria` at the time `now`, in the order of the trove, with the reason to prune them.
+/// Read-only commands are never pruned, favorites only as duplicates.
+/// Duplicates are only pruned in favor of a command which stays, so one command of every group is left
+pub fn prunable(trove: &Trove, criteria: &Criteria, now: SystemTime) -> Vec<Change> {
+    let commands: Vec<&HoardCmd> = trove.commands.iter().filter(|c| !c.is_read_only).collect();
+    let id = |command: &HoardCmd| (command.namespace.clone(), command.name.clone());
+    // The first reason found for a command, by namespace and name
+    let mut reasons: HashMap<(String, String), String> = HashMap::new();
+
+    for command in commands.iter().filter(|c| !c.is_favorite) {
+        if criteria.never_used && command.usage_count == 0 {
+            reasons.insert(id(command), String::from("never used"));
+            continue;
+        }
+        let Some(unused_for) = criteria.unused_for else {
+            continue;
+        };
+        let unused_since = now
+            .checked_sub(unused_for)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        if command.usage_count == 0 && command.created < unused_since {
+            reasons.insert(
+                id(command),
+                format!("never used, created {}", local_date(command.created)),
+            );
+        } else if command.usage_count > 0 && command.last_used < unused_since {
+            reasons.insert(
+                id(command),
+                format!("last used {}", local_date(command.last_used)),
+            );
+        }
+    }
+    if criteria.duplicates || criteria.near_duplicates {
+        let remaining: Vec<&HoardCmd> = commands
+            .iter()
+            .copied()
+            .filter(|c| !reasons.contains_key(&id(c)))
+            .collect();
+        // Near duplicates include the exact ones, so every command is grouped only once
+        let key = |command: &HoardCmd| {
+            if criteria.near_duplicates {
+                normalize(&command.command)
+            } else {
+                command.command.clone()
+            }
+        };
+        for (duplicate, kept) in duplicates(&remaining, key) {
+            let kind = if duplicate.command == kept.command {
+                "duplicate"
+            } else {
+                "near duplicate"
+            };
+            reasons.insert(
+                id(duplicate),
+                format!("{kind} of {}/{}", kept.namespace, kept.name),
+            );
+        }
+    }
+
+    trove
+        .commands
+        .iter()
+        .filter_map(|command| {
+            let reason = reasons.get(&id(command))?;
+            Some(Change {
+                namespace: command.namespace.clone(),
+                name: command.name.clone(),
+                description: reason.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Remove the `pruned` commands from `trove`
+pub fn prune(trove: &mut Trove, pruned: &[Change]) -> Result<()> {
+    for command in pruned {
+        trove.remove_namespaced_command(&command.namespace, &command.name)?;
+    }
+    trove.update_namespaces();
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_prune {
+    use super::*;
+
+    fn pruned(trove: &Trove, criteria: &Criteria, now: SystemTime) -> Vec<String> {
+        prunable(trove, criteria, now)
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn unused_and_never_used_commands() {
+        let now = SystemTime::now();
+        let day = Duration::from_hours(24);
+        let git = |name: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace("git")
+                .with_command(&format!("git {name}"))
+                .with_created(now - day * 400)
+        };
+        let mut favorite = HoardCmd::default().with_name("ps").with_namespace("docker");
+        favorite.is_favorite = true;
+        let mut read_only = HoardCmd::default()
+            .with_name("logs")
+            .with_namespace("docker");
+        read_only.is_read_only = true;
+        let trove = Trove::from_commands(&[
+            git("status").with_usage(1, now - day),
+            git("push").with_usage(300, now - day * 300),
+            git("fetch"),
+            favorite,
+            read_only,
+        ]);
+
+        let never_used = Criteria {
+            never_used: true,
+            ..Criteria::default()
+        };
+        assert_eq!(
+            vec!["git/fetch: never used"],
+            pruned(&trove, &never_used, now)
+        );
+
+        let unused_for = Criteria {
+            unused_for: Some(day * 180),
+            ..Criteria::default()
+        };
+        let pruned = pruned(&trove, &unused_for, now);
+        assert_eq!(2, pruned.len());
+        assert!(pruned[0].starts_with("git/push: last used "));
+        assert!(pruned[1].starts_with("git/fetch: never used, created "));
+    }
+
+    #[test]
+    fn duplicates_and_near_duplicates() {
+        let ls = |name: &str, namespace: &str, command: &str, uses: usize| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace(namespace)
+                .with_command(command)
+                .with_usage(uses, SystemTime::now())
+        };
+        let trove = Trove::from_commands(&[
+            ls("list", "files", "ls -l -a", 1),
+            ls("list", "pet", "ls -l -a", 5),
+            ls("all", "files", "ls  -a   -l", 9),
+            ls("long", "files", "ls -l", 0),
+        ]);
+        let now = SystemTime::now();
+        let duplicates = Criteria {
+            duplicates: true,
+            ..Criteria::default()
+        };
+        assert_eq!(
+            vec!["files/list: duplicate of pet/list"],
+            pruned(&trove, &duplicates, now)
+        );
+
+        let near_duplicates = Criteria {
+            duplicates: true,
+            near_duplicates: true,
+            ..Criteria::default()
+        };
+        assert_eq!(
+            vec![
+                "files/list: near duplicate of files/all",
+                "pet/list: near duplicate of files/all"
+            ],
+            pruned(&trove, &near_duplicates, now)
+        );
+    }
+
+    #[test]
+    fn keep_a_used_duplicate_of_a_stale_command() {
+        let now = SystemTime::now();
+        let day = Duration::from_hours(24);
+        let stale = HoardCmd::default()
+            .with_name("list")
+            .with_namespace("files")
+            .with_command("ls -l")
+            .with_usage(300, now - day * 300);
+        let used = HoardCmd::default()
+            .with_name("list")
+            .with_namespace("pet")
+            .with_command("ls -l")
+            .with_usage(1, now - day);
+        let trove = Trove::from_commands(&[stale, used]);
+        let criteria = Criteria {
+            unused_for: Some(day * 180),
+            duplicates: true,
+            near_duplicates: true,
+            ..Criteria::default()
+        };
+        let pruned = pruned(&trove, &criteria, now);
+        assert_eq!(1, pruned.len());
+        assert!(pruned[0].starts_with("files/list: last used "));
+    }
+
+    #[test]
+    fn prune_only_the_namespaced_command() {
+        let list = |namespace: &str, uses: usize| {
+            HoardCmd::default()
+                .with_name("list")
+                .with_namespace(namespace)
+                .with_command("ls -l")
+                .with_usage(uses, SystemTime::now())
+        };
+        let mut trove = Trove::from_commands(&[list("files", 1), list("pet", 5)]);
+        let criteria = Criteria {
+            duplicates: true,
+            ..Criteria::default()
+        };
+        let pruned = prunable(&trove, &criteria, SystemTime::now());
+        prune(&mut trove, &pruned).unwrap();
+        assert_eq!(1, trove.commands.len());
+        assert_eq!("pet", trove.commands[0].namespace);
+        assert_eq!(vec!["pet"], trove.namespaces());
+    }
+}
@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::cli_commands::{SortField, SortOrder};
+use crate::core::context::UsageContext;
+use crate::core::HoardCmd;
+
+/// Number of use timestamps kept per command to compute its frecency
+pub const MAX_USAGE_HISTORY: usize = 10;
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+
+/// Order in which commands are listed when nothing is searched
+///
+/// - `usage`: Most used commands first
+/// - `frecency`: Commands used often and recently first, so daily used commands beat ones used a lot last year
+/// - `recent`: Most recently used commands first
+/// - `alphabetical`: By name, then namespace
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ranking {
+    Usage,
+    #[default]
+    Frecency,
+    Recent,
+    Alphabetical,
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Usage => write!(f, "usage"),
+            Self::Frecency => write!(f, "frecency"),
+            Self::Recent => write!(f, "recent"),
+            Self::Alphabetical => write!(f, "alphabetical"),
+        }
+    }
+}
+
+/// Order picked by the user, which replaces the ranking
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+impl Sort {
+    /// Sort by `field`, names A to Z, dates and usage counts highest first
+    pub const fn new(field: SortField) -> Self {
+        let order = match field {
+            SortField::Name | SortField::Namespace => SortOrder::Asc,
+            SortField::Created
+            | SortField::Modified
+            | SortField::LastUsed
+            | SortField::UsageCount => SortOrder::Desc,
+        };
+        Self { field, order }
+    }
+
+    pub const fn with_order(self, order: SortOrder) -> Self {
+        Self { order, ..self }
+    }
+
+    /// The next field to sort by, cycling through all fields. `None` after the last one, to rank commands again
+    pub const fn next(sort: Option<Self>) -> Option<Self> {
+        let field = match sort {
+            None => SortField::Name,
+            Some(Self { field, .. }) => match field {
+                SortField::Name => SortField::Namespace,
+                SortField::Namespace => SortField::Created,
+                SortField::Created => SortField::Modified,
+                SortField::Modified => SortField::LastUsed,
+                SortField::LastUsed => SortField::UsageCount,
+                SortField::UsageCount => return None,
+            },
+        };
+        Some(Self::new(field))
+    }
+
+    /// The same field in the opposite order
+    pub const fn reversed(self) -> Self {
+        let order = match self.order {
+            SortOrder::Asc => SortOrder::Desc,
+            SortOrder::Desc => SortOrder::Asc,
+        };
+        self.with_order(order)
+    }
+
+    /// Sort `commands`, owned or shared. Commands with equal fields keep their order
+    pub fn apply<C: Borrow<HoardCmd>>(self, commands: &mut [C]) {
+        commands.sort_by(|a, b| {
+            let (a, b) = (a.borrow(), b.borrow());
+            let ordering = match self.field {
+                SortField::Name => a.name.cmp(&b.name),
+                SortField::Namespace => a.namespace.cmp(&b.namespace),
+                SortField::Created => a.created.cmp(&b.created),
+                SortField::Modified => a.modified.cmp(&b.modified),
+                SortField::LastUsed => a.last_used.cmp(&b.last_used),
+                SortField::UsageCount => a.usage_count.cmp(&b.usage_count),
+            };
+            match self.order {
+                SortOrder::Asc => ordering,
+                SortOrder::Desc => ordering.reverse(),
+            }
+        });
+    }
+}
+
+impl fmt::Display for Sort {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let field = match self.field {
+            SortField::Name => "name",
+            SortField::Namespace => "namespace",
+            SortField::Created => "created",
+            SortField::Modified => "modified",
+            SortField::LastUsed => "last used",
+            SortField::UsageCount => "usage count",
+        };
+        let order = match self.order {
+            SortOrder::Asc => "asc",
+            SortOrder::Desc => "desc",
+        };
+        write!(f, "{field} {order}")
+    }
+}
+
+/// Weight of a single use by its age. Recent uses count much more than old ones
+const fn recency_weight(age: Duration) -> u64 {
+    match age.as_secs() {
+        age if age <= 4 * HOUR => 100,
+        age if age <= DAY => 80,
+        age if age <= 7 * DAY => 60,
+        age if age <= 30 * DAY => 40,
+        age if age <= 90 * DAY => 20,
+        _ => 10,
+    }
+}
+
+/// Score of a command combining how often and how recently it was used.
+///
+/// The uses in the usage history are weighted by their age, and their average weight is multiplied with the
+/// usage count. Commands saved before the history was recorded use their last use instead
+pub fn frecency(command: &HoardCmd, now: SystemTime) -> u64 {
+    let uses: Vec<SystemTime> = if command.usage_history.is_empty() {
+        if command.usage_count == 0 {
+            return 0;
+        }
+        vec![command.last_used]
+    } else {
+        command
+            .usage_history
+            .iter()
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(*secs))
+            .collect()
+    };
+    let total_weight: u64 = uses
+        .iter()
+        .map(|used| recency_weight(now.duration_since(*used).unwrap_or_default()))
+        .sum();
+    let usage_count = command.usage_count.max(uses.len()) as u64;
+    total_weight * usage_count / uses.len() as u64
+}
+
+/// Sort `items` by `ranking` of the command each of them holds, best ranked first.
+/// With a `context`, commands used in its directory come first, then commands used in its repository
+pub fn rank<T, F>(items: &mut [T], command: F, ranking: Ranking, context: Option<&UsageContext>)
+where
+    F: Fn(&T) -> &HoardCmd,
+{
+    match ranking {
+        Ranking::Usage => items.sort_by_key(|i| Reverse(command(i).usage_count)),
+        Ranking::Frecency => {
+            let now = SystemTime::now();
+            items.sort_by_cached_key(|i| {
+                let c = command(i);
+                Reverse((frecency(c, now), c.usage_count))
+            });
+        }
+        Ranking::Recent => items.sort_by_key(|i| Reverse(command(i).last_used)),
+        Ranking::Alphabetical => items.sort_by(|a, b| {
+            let (a, b) = (command(a), command(b));
+            a.name
+                .cmp(&b.name)
+                .then_with(|| a.namespace.cmp(&b.namespace))
+        }),
+    }
+    if let Some(context) = context {
+        items.sort_by_key(|i| Reverse(context.relevance(command(i))));
+    }
+}
+
+/// Order of `a` and `b` by `ranking` at the time `now`, best ranked first, like [`rank`] sorts them.
+/// For putting a single command in place in an already ranked list
+pub fn compare(
+    a: &HoardCmd,
+    b: &HoardCmd,
+    ranking: Ranking,
+    context: Option<&UsageContext>,
+    now: SystemTime,
+) -> Ordering {
+    let relevance = context.map_or(Ordering::Equal, |context| {
+        context.relevance(b).cmp(&context.relevance(a))
+    });
+    relevance.then_with(|| match ranking {
+        Ranking::Usage => b.usage_count.cmp(&a.usage_count),
+        Ranking::Frecency => {
+            (frecency(b, now), b.usage_count).cmp(&(frecency(a, now), a.usage_count))
+        }
+        Ranking::Recent => b.last_used.cmp(&a.last_used),
+        Ranking::Alphabetical => a
+            .name
+            .cmp(&b.name)
+            .then_with(|| a.namespace.cmp(&b.namespace)),
+    })
+}
+
+#[cfg(test)]
+mod test_ranking {
+    use super::*;
+
+    fn used(name: &str, usage_count: usize, days_ago: &[u64]) -> HoardCmd {
+        let now = SystemTime::now();
+        let mut command = HoardCmd::default()
+            .with_name(name)
+            .with_namespace("default");
+        command.usage_count = usage_count;
+        command.usage_history = days_ago
+            .iter()
+            .map(|days| {
+                (now - Duration::from_secs(days * DAY))
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs()
+            })
+            .collect();
+        command
+    }
+
+    fn ranked_names(mut commands: Vec<HoardCmd>, ranking: Ranking) -> Vec<String> {
+        rank(&mut commands, |c| c, ranking, None);
+        commands.into_iter().map(|c| c.name).collect()
+    }
+
+    #[test]
+    fn frecency_prefers_recent_uses() {
+        let last_year = used(
+            "last_year",
+            50,
+            &[400, 390, 380, 370, 360, 350, 340, 330, 320, 310],
+        );
+        let this_week = used("this_week", 10, &[0, 1, 2, 3, 4, 5, 6, 0, 1, 2]);
+        let commands = vec![last_year, this_week];
+        assert_eq!(
+            vec!["last_year", "this_week"],
+            ranked_names(commands.clone(), Ranking::Usage)
+        );
+        assert_eq!(
+            vec!["this_week", "last_year"],
+            ranked_names(commands, Ranking::Frecency)
+        );
+    }
+
+    #[test]
+    fn frecency_without_history() {
+        let mut command = HoardCmd::default();
+        assert_eq!(0, frecency(&command, SystemTime::now()));
+        command.usage_count = 3;
+        assert_eq!(300, frecency(&command, SystemTime::now()));
+    }
+
+    #[test]
+    fn recent_and_alphabetical() {
+        let mut old = used("b", 1, &[]);
+        old.last_used = SystemTime::now() - Duration::from_secs(DAY);
+        let new = used("c", 1, &[]);
+        let mut unused = used("a", 0, &[]);
+        unused.last_used = UNIX_EPOCH;
+        let commands = vec![old, new, unused];
+        assert_eq!(
+            vec!["c", "b", "a"],
+            ranked_names(commands.clone(), Ranking::Recent)
+        );
+        assert_eq!(
+            vec!["a", "b", "c"],
+            ranked_names(commands, Ranking::Alphabetical)
+        );
+    }
+
+    #[test]
+    fn boost_commands_used_here() {
+        let context = UsageContext::of(std::path::PathBuf::from("/work/project"));
+        let mut used_here = used("used_here", 1, &[100]);
+        used_here.mut_record_context(&context);
+        let mut commands = vec![used("daily", 10, &[0, 1, 2]), used_here];
+        rank(&mut commands, |c| c, Ranking::Frecency, Some(&context));
+        assert_eq!("used_here", commands[0].name);
+    }
+
+    #[test]
+    fn sort_by_field_and_order() {
+        let mut a = used("a", 3, &[]);
+        a.namespace = "zsh".to_string();
+        let mut b = used("b", 1, &[]);
+        b.created = UNIX_EPOCH;
+        let mut c = used("c", 2, &[]);
+        c.created = UNIX_EPOCH + Duration::from_secs(DAY);
+        let sorted = |sort: Sort| {
+            let mut commands = vec![b.clone(), c.clone(), a.clone()];
+            sort.apply(&mut commands);
+            commands.into_iter().map(|c| c.name).collect::<Vec<_>>()
+        };
+        assert_eq!(vec!["a", "b", "c"], sorted(Sort::new(SortField::Name)));
+        assert_eq!(
+            vec!["c", "b", "a"],
+            sorted(Sort::new(SortField::Name).reversed())
+        );
+        assert_eq!(vec!["b", "c", "a"], sorted(Sort::new(SortField::Namespace)));
+        assert_eq!(
+            vec!["a", "c", "b"],
+            sorted(Sort::new(SortField::UsageCount))
+        );
+        assert_eq!(
+            vec!["b", "c", "a"],
+            sorted(Sort::new(SortField::Created).with_order(SortOrder::Asc))
+        );
+    }
+
+    #[test]
+    fn cycle_sort_fields() {
+        let mut sort = None;
+        let mut fields = Vec::new();
+        loop {
+            sort = Sort::next(sort);
+            match sort {
+                Some(s) => fields.push(s.field),
+                None => break,
+            }
+        }
+        assert_eq!(6, fields.len());
+        assert_eq!(SortField::Name, fields[0]);
+        assert_eq!("last used desc", Sort::new(SortField::LastUsed).to_string());
+    }
+
+    #[test]
+    fn usage_history_is_capped() {
+        let mut command = HoardCmd::default();
+        for _ in 0..MAX_USAGE_HISTORY + 5 {
+            command.mut_record_use();
+        }
+        assert_eq!(MAX_USAGE_HISTORY + 5, command.usage_count);
+        assert_eq!(MAX_USAGE_HISTORY, command.usage_history.len());
+    }
+}
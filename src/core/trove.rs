@@ -6,13 +6,34 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::{fs, path::Path, path::PathBuf};
 
+use crate::cli_commands::ConflictStrategy;
 use crate::config::HoardConfig;
+use crate::core::context::UsageContext;
+use crate::core::diff::{CommandChange, MergeDiff};
 use crate::core::error::HoardErr;
 use crate::core::parameters::Parameterized;
 use crate::core::HoardCmd;
 
 const CARGO_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// What happens to an incoming command when merging troves
+///
+/// Every variant but `Add` and `Identical` carries the local command with the same name and namespace
+enum MergeAction {
+    /// No local command with that name, add it
+    Add,
+    /// The same command is already present, skip it
+    Identical,
+    /// Replace the local command
+    Replace(HoardCmd),
+    /// Add the command with a new name
+    Rename(HoardCmd),
+    /// Keep the local command and skip the incoming one
+    Keep(HoardCmd),
+    /// Ask the user what to do
+    Ask(HoardCmd),
+}
+
 /// Container for all stored hoard commands.
 /// A `treasure trove` of commands
 ///
@@ -108,7 +129,8 @@ impl Trove {
 
     /// Save the trove collection to `path` as a yaml file
     pub fn save_trove_file(&self, path: &Path) {
-        let s = self.to_yaml();
+        // Read-only commands of subscribed troves are cached separately
+        let s = self.clone().without_read_only_commands().to_yaml();
         fs::write(path, s).expect("Unable to write config file");
     }
 
@@ -125,13 +147,6 @@ impl Trove {
         colliding_commands.into_iter().next()
     }
 
-    /// Get all commands in the trove collection sorted by usage count
-    pub fn get_commands_sorted_by_usage(&self) -> Vec<HoardCmd> {
-        let mut commands = self.commands.clone();
-        commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
-        commands
-    }
-
     /// Given a `HoardCmd`, check if there is a command with the same name, namespace and saved command already in the collection.
     /// A command with those same parameters is considered to be the same command
     /// If there is, return `true`
@@ -204,11 +219,28 @@ impl Trove {
     /// 
     /// Returns `Err(anyhow::Error)` if the command to remove is not in the trove
     pub fn remove_command(&mut self, name: &str) -> Result<(), anyhow::Error> {
-        let command_position = self.commands.iter().position(|x| x.name == name);
-        if command_position.is_none() {
-            return Err(anyhow!("Command not found [{}]", name));
+        self.remove_commands_where(|x| x.name == name)
+            .map_err(|_| anyhow!("Command not found [{}]", name))
+    }
+
+    /// Remove the command `name` of `namespace`, leaving commands with the same name in other namespaces
+    pub fn remove_namespaced_command(
+        &mut self,
+        namespace: &str,
+        name: &str,
+    ) -> Result<(), anyhow::Error> {
+        self.remove_commands_where(|x| x.namespace == namespace && x.name == name)
+            .map_err(|_| anyhow!("Command not found [{}/{}]", namespace, name))
+    }
+
+    fn remove_commands_where<F>(&mut self, matches: F) -> Result<(), anyhow::Error>
+    where
+        F: Fn(&HoardCmd) -> bool,
+    {
+        if !self.commands.iter().any(&matches) {
+            return Err(anyhow!("No matching command"));
         }
-        self.commands.retain(|x| &*x.name != name);
+        self.commands.retain(|x| !matches(x));
         Ok(())
     }
 
@@ -218,8 +250,10 @@ impl Trove {
             return Err(anyhow!("Command not found [{}]", command.name));
         }
         let mut updated_command = command.clone();
-        updated_command.mut_increase_usage_count();
-        updated_command.mut_update_last_used();
+        updated_command.mut_record_use();
+        if let Some(context) = UsageContext::current() {
+            updated_command.mut_record_context(&context);
+        }
         self.commands[command_position.unwrap()] = updated_command;
         Ok(())
     }
@@ -233,6 +267,15 @@ impl Trove {
         Ok(())
     }
 
+    /// Collect the namespaces of all commands again, after commands moved between namespaces
+    pub fn update_namespaces(&mut self) {
+        self.namespaces = self
+            .namespaces()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+    }
+
     pub fn namespaces(&self) -> Vec<&str> {
         // Returns all namespaces in the trove
         let mut namespaces: Vec<_> = self
@@ -263,7 +306,7 @@ impl Trove {
 
     pub fn update_command_by_name(&mut self, command: &HoardCmd) -> &mut Self {
         for c in &mut self.commands.iter_mut() {
-            if c.name == command.name {
+            if c.name == command.name && !c.is_read_only {
                 *c = command.clone();
                 c.mut_update_last_used();
             }
@@ -271,17 +314,115 @@ impl Trove {
         self
     }
 
+    /// Add all commands of `other` as read-only commands, which are never saved with the trove collection
+    pub fn with_read_only_commands(mut self, other: &Self) -> Self {
+        for command in &other.commands {
+            let mut command = command.clone();
+            command.is_read_only = true;
+            self.namespaces.insert(command.namespace.clone());
+            self.commands.push(command);
+        }
+        self
+    }
+
+    /// Drop all read-only commands from the trove collection
+    pub fn without_read_only_commands(mut self) -> Self {
+        self.commands.retain(|c| !c.is_read_only);
+        self.namespaces = self
+            .namespaces()
+            .into_iter()
+            .map(ToString::to_string)
+            .collect();
+        self
+    }
+
     /// check if the trove collection is empty
     pub fn is_empty(&self) -> bool {
         self.commands.is_empty()
     }
 
-    pub fn merge_trove(&mut self, other: &Self) -> bool {
-        other
-            .commands
-            .iter()
-            .map(|c| self.add_command(c.clone(), true))
-            .any(|x| x.is_ok())
+    /// Merge all commands of `other` into the trove collection
+    ///
+    /// Commands which are already present are skipped.
+    /// `on_conflict` decides what happens to a command with the same name and namespace as a local one, but a different command string
+    ///
+    /// Returns `true` if the trove collection changed
+    pub fn merge_trove(&mut self, other: &Self, on_conflict: ConflictStrategy) -> bool {
+        let mut dirty = false;
+        for command in &other.commands {
+            dirty |= self.merge_command(command.clone(), on_conflict);
+        }
+        dirty
+    }
+
+    /// Decide what merging `command` into the trove collection does, following `on_conflict`
+    fn merge_action(&self, command: &HoardCmd, on_conflict: ConflictStrategy) -> MergeAction {
+        let Some(collision) = self.get_command_collision(command) else {
+            return MergeAction::Add;
+        };
+        if self.is_command_present(command) {
+            return MergeAction::Identical;
+        }
+        match on_conflict {
+            ConflictStrategy::Replace => MergeAction::Replace(collision),
+            ConflictStrategy::Keep => MergeAction::Keep(collision),
+            ConflictStrategy::Rename => MergeAction::Rename(collision),
+            ConflictStrategy::Newest => {
+                if command.modified > collision.modified {
+                    MergeAction::Replace(collision)
+                } else {
+                    MergeAction::Keep(collision)
+                }
+            }
+            ConflictStrategy::Ask => MergeAction::Ask(collision),
+        }
+    }
+
+    fn merge_command(&mut self, command: HoardCmd, on_conflict: ConflictStrategy) -> bool {
+        match self.merge_action(&command, on_conflict) {
+            MergeAction::Add | MergeAction::Replace(_) => {
+                self.add_command(command, true).unwrap_or(false)
+            }
+            MergeAction::Rename(_) => self.add_command(command, false).unwrap_or(false),
+            MergeAction::Keep(_) | MergeAction::Identical => false,
+            MergeAction::Ask(collision) => {
+                let (to_add, to_remove) = command.resolve_name_conflict(collision, self);
+                if let Some(to_remove) = to_remove {
+                    self.commands.retain(|x| x != &to_remove);
+                }
+                to_add.is_some_and(|c| self.add_command(c, true).unwrap_or(false))
+            }
+        }
+    }
+
+    /// Report what `merge_trove` would change, without changing the trove collection
+    ///
+    /// Conflicts which would be resolved by asking the user are only reported
+    pub fn diff_trove(&self, other: &Self, on_conflict: ConflictStrategy) -> MergeDiff {
+        let mut diff = MergeDiff::default();
+        // Merge into a copy, so commands colliding with earlier incoming commands are reported correctly
+        let mut merged = self.clone();
+        for command in &other.commands {
+            match merged.merge_action(command, on_conflict) {
+                MergeAction::Add => diff.added.push(command.into()),
+                MergeAction::Identical => diff.identical.push(command.into()),
+                MergeAction::Replace(local) => {
+                    diff.replaced.push(CommandChange::between(&local, command));
+                }
+                MergeAction::Rename(local) => {
+                    diff.renamed.push(CommandChange::between(&local, command));
+                }
+                MergeAction::Keep(local) => {
+                    diff.kept.push(CommandChange::between(&local, command));
+                }
+                MergeAction::Ask(local) => {
+                    diff.conflicts.push(CommandChange::between(&local, command));
+                    continue;
+                }
+            }
+            merged.merge_command(command.clone(), on_conflict);
+        }
+        diff
     }
 
     pub fn print_trove(&self) {
@@ -524,6 +665,108 @@ mod test_commands {
         assert!(trove.is_empty());
     }
 
+    fn conflicting_troves() -> (Trove, Trove) {
+        let mut local = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status");
+        local.modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(100);
+        let mut incoming = local.clone().with_command("git status --short");
+        incoming.modified = std::time::UNIX_EPOCH + std::time::Duration::from_secs(200);
+        let other = HoardCmd::default()
+            .with_name("log")
+            .with_namespace("git")
+            .with_command("git log");
+        (
+            Trove::from_commands(&[local]),
+            Trove::from_commands(&[incoming, other]),
+        )
+    }
+
+    fn commands_of(trove: &Trove) -> Vec<&str> {
+        trove.commands.iter().map(|c| c.command.as_str()).collect()
+    }
+
+    #[test]
+    fn test_merge_replace() {
+        let (mut local, incoming) = conflicting_troves();
+        assert!(local.merge_trove(&incoming, ConflictStrategy::Replace));
+        assert_eq!(vec!["git status --short", "git log"], commands_of(&local));
+    }
+
+    #[test]
+    fn test_merge_keep() {
+        let (mut local, incoming) = conflicting_troves();
+        assert!(local.merge_trove(&incoming, ConflictStrategy::Keep));
+        assert_eq!(vec!["git status", "git log"], commands_of(&local));
+    }
+
+    #[test]
+    fn test_merge_rename() {
+        let (mut local, incoming) = conflicting_troves();
+        assert!(local.merge_trove(&incoming, ConflictStrategy::Rename));
+        assert_eq!(
+            vec!["git status", "git status --short", "git log"],
+            commands_of(&local)
+        );
+        assert!(local.commands[1].name.starts_with("status-"));
+    }
+
+    #[test]
+    fn test_merge_newest() {
+        let (mut local, incoming) = conflicting_troves();
+        assert!(local.merge_trove(&incoming, ConflictStrategy::Newest));
+        assert_eq!(vec!["git status --short", "git log"], commands_of(&local));
+
+        let (incoming, mut local) = conflicting_troves();
+        local.merge_trove(&incoming, ConflictStrategy::Newest);
+        assert_eq!(vec!["git status --short", "git log"], commands_of(&local));
+    }
+
+    #[test]
+    fn test_merge_identical_trove() {
+        let (mut local, _) = conflicting_troves();
+        let same = local.clone();
+        assert!(!local.merge_trove(&same, ConflictStrategy::Ask));
+    }
+
+    #[test]
+    fn test_diff_trove() {
+        let (local, mut incoming) = conflicting_troves();
+        incoming.commands.push(incoming.commands[1].clone());
+        let diff = local.diff_trove(&incoming, ConflictStrategy::Replace);
+        assert_eq!(1, diff.added.len());
+        assert_eq!("log", diff.added[0].name);
+        assert_eq!(1, diff.replaced.len());
+        assert_eq!("command", diff.replaced[0].changes[0].field);
+        assert_eq!("git status --short", diff.replaced[0].changes[0].incoming);
+        // The duplicated incoming command is identical to the one added before it
+        assert_eq!(1, diff.identical.len());
+        assert_eq!(vec!["git status"], commands_of(&local));
+    }
+
+    #[test]
+    fn test_diff_trove_ask() {
+        let (local, incoming) = conflicting_troves();
+        let diff = local.diff_trove(&incoming, ConflictStrategy::Ask);
+        assert_eq!(1, diff.conflicts.len());
+        assert!(diff.replaced.is_empty());
+    }
+
+    #[test]
+    fn test_read_only_commands_are_not_saved() {
+        let (local, subscribed) = conflicting_troves();
+        let trove = local.with_read_only_commands(&subscribed);
+        assert_eq!(3, trove.commands.len());
+        assert!(trove.commands[1..].iter().all(|c| c.is_read_only));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trove.yml");
+        trove.save_trove_file(&path);
+        let saved = Trove::load_trove_file(&Some(path));
+        assert_eq!(vec!["git status"], commands_of(&saved));
+    }
+
     #[test]
     fn test_is_empty_new_trove() {
         // create a new trove and check if it is empty
@@ -1,20 +1,339 @@
+use std::borrow::Cow;
+
+use crate::cli_commands::SelectionArgs;
 use crate::core::trove::Trove;
 use crate::core::HoardCmd;
+use crate::fuzzy::{signature, Pattern};
+use crate::query::{Query, QueryError};
 
-pub fn query_trove(trove: &Trove, query_term: &str) -> Trove {
-    // Filter out commands of `trove` based on `query_string`
-    // Construct QueryString object from &str object to validate / extract special parameters
-    let commands: Vec<HoardCmd> = trove
-        .commands
-        .clone()
+/// Fields of a command the search looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Namespace,
+    Tags,
+    Command,
+    Description,
+}
+
+impl Field {
+    pub const ALL: [Self; 5] = [
+        Self::Name,
+        Self::Namespace,
+        Self::Tags,
+        Self::Command,
+        Self::Description,
+    ];
+
+    /// Matches in fields with a higher weight rank a command higher, so a matching name beats a matching description
+    const fn weight(self) -> i64 {
+        match self {
+            Self::Name => 4,
+            Self::Namespace | Self::Tags | Self::Command => 2,
+            Self::Description => 1,
+        }
+    }
+
+    pub fn text(self, command: &HoardCmd) -> Cow<'_, str> {
+        match self {
+            Self::Name => Cow::Borrowed(&command.name),
+            Self::Namespace => Cow::Borrowed(&command.namespace),
+            Self::Tags => Cow::Owned(command.get_tags_as_string()),
+            Self::Command => Cow::Borrowed(&command.command),
+            Self::Description => Cow::Borrowed(&command.description),
+        }
+    }
+}
+
+/// Texts of all fields of a command, in the order of [`Field::ALL`], with their character signatures.
+/// The tags are joined with `,`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldTexts<'a> {
+    texts: [Cow<'a, str>; 5],
+    signatures: [u64; 5],
+}
+
+impl<'a> FieldTexts<'a> {
+    pub fn of(command: &'a HoardCmd) -> Self {
+        let texts = Field::ALL.map(|field| field.text(command));
+        let signatures = [0, 1, 2, 3, 4].map(|i| signature(&texts[i]));
+        Self { texts, signatures }
+    }
+
+    /// Texts which do not borrow the command, to keep them around while the command changes
+    pub fn into_owned(self) -> FieldTexts<'static> {
+        FieldTexts {
+            texts: self.texts.map(|text| Cow::Owned(text.into_owned())),
+            signatures: self.signatures,
+        }
+    }
+
+    pub fn get(&self, field: Field) -> &str {
+        &self.texts[field as usize]
+    }
+
+    /// Signature of all fields together
+    pub fn signature(&self) -> u64 {
+        self.signatures.iter().fold(0, |a, b| a | b)
+    }
+}
+
+/// How a command matches a search query
+///
+/// - `score`: Sum of the weighted scores of all text terms of the query. Higher is better
+/// - `positions`: For every query term, the field it matched best and the positions of the matched characters
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CommandMatch {
+    pub score: i64,
+    positions: Vec<(Field, Vec<usize>)>,
+}
+
+impl CommandMatch {
+    pub fn new(field: Field, score: i64, positions: Vec<usize>) -> Self {
+        Self {
+            score,
+            positions: vec![(field, positions)],
+        }
+    }
+
+    /// Add the score and positions of another term of the query
+    pub fn extend(&mut self, other: Self) {
+        self.score += other.score;
+        self.positions.extend(other.positions);
+    }
+
+    /// Sorted character positions of all matches in `field`, to highlight them
+    pub fn positions(&self, field: Field) -> Vec<usize> {
+        let mut positions: Vec<usize> = self
+            .positions
+            .iter()
+            .filter(|(f, _)| *f == field)
+            .flat_map(|(_, p)| p.iter().copied())
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+        positions
+    }
+}
+
+/// Match `pattern` against `fields` of a command. The field with the best weighted score counts,
+/// only its positions are collected, and only `with_positions`.
+/// Fields which cannot beat the best match so far are skipped, so `fields` should be ordered by weight
+pub fn match_fields(
+    texts: &FieldTexts,
+    fields: &[Field],
+    pattern: &Pattern,
+    with_positions: bool,
+) -> Option<CommandMatch> {
+    let max_score = pattern.max_score();
+    let mut best: Option<(Field, i64)> = None;
+    for field in fields {
+        if best.is_some_and(|(_, score)| score >= max_score * field.weight())
+            || !pattern.could_match(texts.signatures[*field as usize])
+        {
+            continue;
+        }
+        if let Some(score) = pattern.score(texts.get(*field)) {
+            let score = score * field.weight();
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((*field, score));
+            }
+        }
+    }
+    let (field, score) = best?;
+    if !with_positions {
+        return Some(CommandMatch {
+            score,
+            positions: Vec::new(),
+        });
+    }
+    pattern
+        .find(texts.get(field))
+        .map(|m| CommandMatch::new(field, m.score * field.weight(), m.positions))
+}
+
+/// Search `commands` for `query`, best matches first. Commands with the same score keep their order
+pub fn search_commands<'a>(
+    commands: &'a [HoardCmd],
+    query: &Query,
+) -> Vec<(&'a HoardCmd, CommandMatch)> {
+    let mut matches: Vec<_> = commands
+        .iter()
+        .filter_map(|c| query.matches(c).map(|m| (c, m)))
+        .collect();
+    matches.sort_by_key(|(_, m)| std::cmp::Reverse(m.score));
+    matches
+}
+
+pub fn query_trove(trove: &Trove, query: &Query) -> Trove {
+    // Filter out commands of `trove` based on `query`, best matches first
+    let commands: Vec<HoardCmd> = search_commands(&trove.commands, query)
         .into_iter()
-        .filter(|c| {
-            c.name.contains(query_term)
-                || c.namespace.contains(query_term)
-                || c.get_tags_as_string().contains(query_term)
-                || c.command.contains(query_term)
-                || c.description.contains(query_term)
+        .map(|(c, _)| c.clone())
+        .collect();
+    Trove::from_commands(&commands)
+}
+
+/// Selects commands without user interaction, as given by command line flags.
+/// All set criteria have to match for a command to be selected
+///
+/// - `namespaces`: The command lives in one of these namespaces
+/// - `tags`: The command has one of these tags
+/// - `query`: The command matches the query
+/// - `favorites_only`: The command is marked as favorite
+#[derive(Debug, Default)]
+pub struct CommandSelection {
+    pub namespaces: Vec<String>,
+    pub tags: Vec<String>,
+    pub query: Option<Query>,
+    pub favorites_only: bool,
+}
+
+impl CommandSelection {
+    /// `true` if no criteria are set
+    pub const fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+            && self.tags.is_empty()
+            && self.query.is_none()
+            && !self.favorites_only
+    }
+
+    pub fn matches(&self, command: &HoardCmd) -> bool {
+        (self.namespaces.is_empty() || self.namespaces.contains(&command.namespace))
+            && (self.tags.is_empty() || command.tags.iter().any(|t| self.tags.contains(t)))
+            && self
+                .query
+                .as_ref()
+                .is_none_or(|query| query.matches(command).is_some())
+            && (!self.favorites_only || command.is_favorite)
+    }
+}
+
+impl TryFrom<&SelectionArgs> for CommandSelection {
+    type Error = QueryError;
+
+    fn try_from(args: &SelectionArgs) -> Result<Self, Self::Error> {
+        Ok(Self {
+            namespaces: args.namespace.clone(),
+            tags: args.tag.clone(),
+            query: args.filter.as_deref().map(Query::parse).transpose()?,
+            favorites_only: args.favorites,
         })
+    }
+}
+
+/// Collect all commands of `trove` matching `selection`
+pub fn select_commands(trove: &Trove, selection: &CommandSelection) -> Trove {
+    let commands: Vec<HoardCmd> = trove
+        .commands
+        .iter()
+        .filter(|c| selection.matches(c))
+        .cloned()
         .collect();
     Trove::from_commands(&commands)
 }
+
+#[cfg(test)]
+mod test_filter {
+    use super::*;
+
+    fn trove() -> Trove {
+        let mut status = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status")
+            .with_tags_raw("vcs");
+        status.mut_set_favorite(true);
+        let push = HoardCmd::default()
+            .with_name("push")
+            .with_namespace("git")
+            .with_command("git push")
+            .with_tags_raw("vcs,remote");
+        let ps = HoardCmd::default()
+            .with_name("ps")
+            .with_namespace("docker")
+            .with_command("docker ps")
+            .with_tags_raw("remote");
+        Trove::from_commands(&[status, push, ps])
+    }
+
+    fn query(query: &str) -> Query {
+        Query::parse(query).unwrap()
+    }
+
+    fn selected_names(selection: &CommandSelection) -> Vec<String> {
+        select_commands(&trove(), selection)
+            .commands
+            .into_iter()
+            .map(|c| c.name)
+            .collect()
+    }
+
+    #[test]
+    fn empty_selection_selects_everything() {
+        let selection = CommandSelection::default();
+        assert!(selection.is_empty());
+        assert_eq!(vec!["status", "push", "ps"], selected_names(&selection));
+    }
+
+    #[test]
+    fn select_by_namespaces_and_tags() {
+        let selection = CommandSelection {
+            namespaces: vec!["git".to_string(), "docker".to_string()],
+            tags: vec!["remote".to_string()],
+            ..CommandSelection::default()
+        };
+        assert_eq!(vec!["push", "ps"], selected_names(&selection));
+    }
+
+    #[test]
+    fn fuzzy_query_ranks_names_first() {
+        let mut trove = trove();
+        trove.commands.push(
+            HoardCmd::default()
+                .with_name("log")
+                .with_namespace("git")
+                .with_command("git log")
+                .with_description("Show the status of the git history"),
+        );
+        let names: Vec<String> = query_trove(&trove, &query("gst"))
+            .commands
+            .into_iter()
+            .map(|c| c.name)
+            .collect();
+        assert_eq!(vec!["status", "log"], names);
+    }
+
+    #[test]
+    fn every_query_term_has_to_match() {
+        let trove = trove();
+        let ps = &trove.commands[2];
+        assert!(query("docker remote").matches(ps).is_some());
+        assert!(query("docker vcs").matches(ps).is_none());
+        assert!(query("").matches(ps).is_some());
+    }
+
+    #[test]
+    fn match_positions_by_field() {
+        let trove = trove();
+        let matched = query("sts vcs").matches(&trove.commands[0]).unwrap();
+        assert_eq!(vec![0, 1, 5], matched.positions(Field::Name));
+        assert_eq!(vec![0, 1, 2], matched.positions(Field::Tags));
+        assert!(matched.positions(Field::Description).is_empty());
+    }
+
+    #[test]
+    fn select_by_query_and_favorites() {
+        let by_query = CommandSelection {
+            query: Some(query("git")),
+            ..CommandSelection::default()
+        };
+        assert_eq!(vec!["status", "push"], selected_names(&by_query));
+
+        let favorites = CommandSelection {
+            favorites_only: true,
+            ..CommandSelection::default()
+        };
+        assert_eq!(vec!["status"], selected_names(&favorites));
+    }
+}
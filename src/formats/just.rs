@@ -0,0 +1,358 @@
+use regex::{Captures, Regex};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::formats::{add_with_unique_name, map_parameters};
+
+/// Stands in for an escaped `{{` while interpolations are replaced
+const ESCAPED_BRACES: &str = "\u{0}";
+
+/// A recipe parameter of a justfile header, like `target`, `mode='debug'`, `*args` or `$name`
+struct RecipeParameter {
+    name: String,
+    default: Option<String>,
+    is_exported: bool,
+}
+
+/// Split a recipe header into words, keeping quoted defaults with whitespace together.
+/// Returns the words before the colon ending the header, or `None` if the line is no recipe header
+fn split_recipe_header(line: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => {
+                quote = None;
+                word.push(c);
+            }
+            (Some(_), _) => word.push(c),
+            (None, '\'' | '"' | '`') => {
+                quote = Some(c);
+                word.push(c);
+            }
+            // `:=` assigns a variable or setting
+            (None, ':') if chars.peek() == Some(&'=') => return None,
+            (None, ':') => {
+                if !word.is_empty() {
+                    words.push(word);
+                }
+                return Some(words);
+            }
+            (None, c) if c.is_whitespace() => {
+                if !word.is_empty() {
+                    words.push(std::mem::take(&mut word));
+                }
+            }
+            (None, c) => word.push(c),
+        }
+    }
+    None
+}
+
+fn parse_parameter(word: &str) -> RecipeParameter {
+    let word = word.trim_start_matches(['*', '+']);
+    let (word, is_exported) = word
+        .strip_prefix('$')
+        .map_or((word, false), |word| (word, true));
+    let (name, default) = word
+        .split_once('=')
+        .map_or((word, None), |(name, default)| (name, Some(default)));
+    RecipeParameter {
+        name: name.to_string(),
+        default: default.map(|d| d.trim_matches(['\'', '"']).to_string()),
+        is_exported,
+    }
+}
+
+/// Replace `{{parameter}}` interpolations, and `$parameter` for exported parameters, with hoard parameters.
+/// Interpolations of anything else are kept, with a warning
+fn convert_recipe_line(
+    line: &str,
+    parameters: &[RecipeParameter],
+    parameter_token: &str,
+    parameter_ending_token: &str,
+    warnings: &mut Vec<String>,
+) -> String {
+    let interpolation = Regex::new(r"\{\{(.*?)\}\}").unwrap();
+    let hoard_parameter = |name: &str| format!("{parameter_token}{name}{parameter_ending_token}");
+    let line = line.replace("{{{{", ESCAPED_BRACES);
+    let mut line = interpolation
+        .replace_all(&line, |captures: &Captures| {
+            let expression = captures[1].trim();
+            if parameters.iter().any(|p| p.name == expression) {
+                hoard_parameter(expression)
+            } else {
+                warnings.push(format!(
+                    "Kept expression {{{{{expression}}}}}, only recipe parameters are converted"
+                ));
+                captures[0].to_string()
+            }
+        })
+        .replace(ESCAPED_BRACES, "{{");
+    for parameter in parameters.iter().filter(|p| p.is_exported) {
+        let exported = Regex::new(&format!(
+            r"\$(\{{{0}\}}|{0}\b)",
+            regex::escape(&parameter.name)
+        ))
+        .unwrap();
+        line = exported
+            .replace_all(&line, hoard_parameter(&parameter.name).as_str())
+            .to_string();
+    }
+    line
+}
+
+/// Parse a justfile into a trove. All recipes are put into `namespace`.
+///
+/// - Comment lines right above a recipe become its description, the recipe name becomes the command name
+/// - Recipe parameters become hoard parameters. hoard parameters have no default values,
+///   so defaults are dropped with a warning
+/// - The lines of a recipe are joined with `&&`
+/// - Private recipes and recipes running a script with a shebang are skipped
+pub fn trove_from_just(
+    justfile: &str,
+    namespace: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> (Trove, Vec<String>) {
+    let mut trove = Trove::default();
+    let mut warnings = Vec::new();
+    let mut comments: Vec<&str> = Vec::new();
+    let mut is_private = false;
+    let mut lines = justfile.lines().peekable();
+
+    while let Some(line) = lines.next() {
+        if line.trim().is_empty() {
+            comments.clear();
+            is_private = false;
+            continue;
+        }
+        if let Some(comment) = line.strip_prefix('#') {
+            if !comment.starts_with('!') {
+                comments.push(comment.trim());
+            }
+            continue;
+        }
+        if line.starts_with('[') {
+            // Attributes like `[private]` or `[no-cd]`
+            is_private |= line.contains("private");
+            continue;
+        }
+        let header = if line.starts_with(char::is_whitespace) {
+            None
+        } else {
+            split_recipe_header(line)
+        };
+        let Some(header) = header else {
+            comments.clear();
+            continue;
+        };
+
+        // Body lines of the recipe are indented
+        let mut body = Vec::new();
+        while let Some(body_line) = lines.next_if(|l| l.starts_with(char::is_whitespace)) {
+            if !body_line.trim().is_empty() {
+                body.push(body_line.trim());
+            }
+        }
+
+        let name = header[0].trim_start_matches('@');
+        let description = comments.join("\n");
+        comments.clear();
+        if std::mem::take(&mut is_private) || name.starts_with('_') || body.is_empty() {
+            continue;
+        }
+        if body[0].starts_with("#!") {
+            warnings.push(format!(
+                "Skipped recipe '{name}', scripts with a shebang are not supported"
+            ));
+            continue;
+        }
+
+        let parameters: Vec<RecipeParameter> = header[1..]
+            .iter()
+            .map(|word| parse_parameter(word))
+            .collect();
+        for parameter in &parameters {
+            if let Some(default) = &parameter.default {
+                warnings.push(format!(
+                    "Dropped default value '{default}' of parameter '{}' in recipe '{name}'",
+                    parameter.name
+                ));
+            }
+        }
+        let command = body
+            .iter()
+            .map(|line| {
+                convert_recipe_line(
+                    line.trim_start_matches(['@', '-']),
+                    &parameters,
+                    parameter_token,
+                    parameter_ending_token,
+                    &mut warnings,
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" && ");
+        let description = if description.is_empty() {
+            command.clone()
+        } else {
+            description
+        };
+        let new_command = HoardCmd::default()
+            .with_name(name)
+            .with_namespace(namespace)
+            .with_command(&command)
+            .with_description(&description);
+        add_with_unique_name(&mut trove, new_command);
+    }
+    (trove, warnings)
+}
+
+/// Turn `name` into a valid recipe or parameter name: a letter or `_` followed by alphanumerics, `_` or `-`
+fn to_identifier(name: &str, fallback_prefix: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => name,
+        _ => format!("{fallback_prefix}{name}"),
+    }
+}
+
+/// Write a trove as a justfile with one recipe for every command
+pub fn trove_to_just(trove: &Trove, parameter_token: &str, parameter_ending_token: &str) -> String {
+    let mut justfile = String::new();
+    let mut used_names = HashSet::new();
+    for command in &trove.commands {
+        let base_name = to_identifier(&command.name, "cmd_");
+        let mut name = base_name.clone();
+        let mut suffix = 1;
+        while used_names.contains(&name) {
+            suffix += 1;
+            name = format!("{base_name}_{suffix}");
+        }
+        used_names.insert(name.clone());
+
+        let mut parameters: Vec<String> = Vec::new();
+        let body = map_parameters(
+            &command.command.replace("{{", "{{{{"),
+            parameter_token,
+            parameter_ending_token,
+            |parameter| {
+                let parameter = to_identifier(parameter, "p_");
+                if !parameters.contains(&parameter) {
+                    parameters.push(parameter.clone());
+                }
+                format!("{{{{{parameter}}}}}")
+            },
+        );
+
+        for line in command.description.lines() {
+            let _ = writeln!(justfile, "# {line}");
+        }
+        let _ = write!(justfile, "{name}");
+        for parameter in &parameters {
+            let _ = write!(justfile, " {parameter}");
+        }
+        let _ = writeln!(justfile, ":");
+        for line in body.lines() {
+            let _ = writeln!(justfile, "    {line}");
+        }
+        justfile.push('\n');
+    }
+    justfile
+}
+
+#[cfg(test)]
+mod test_just {
+    use super::*;
+
+    const JUSTFILE: &str = r#"set shell := ["bash", "-c"]
+version := "1.0"
+
+alias b := build
+
+# Build the project
+# in release mode
+build mode='release':
+    cargo build --{{mode}}
+
+# Deploy to a host
+@deploy host $user *flags: build
+    ssh $user@{{ host }} ./deploy.sh {{flags}} {{version}}
+    echo done
+
+_helper:
+    echo private
+
+[private]
+hidden:
+    echo hidden
+
+script:
+    #!/usr/bin/env python3
+    print("hi")
+
+echo:
+    echo '{{{{literal}}'
+"#;
+
+    #[test]
+    fn import_justfile() {
+        let (trove, warnings) = trove_from_just(JUSTFILE, "project", "#", "!");
+        let names: Vec<&str> = trove.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["build", "deploy", "echo"], names);
+
+        let build = &trove.commands[0];
+        assert_eq!("cargo build --#mode!", build.command);
+        assert_eq!("Build the project\nin release mode", build.description);
+        assert_eq!("project", build.namespace);
+
+        let deploy = &trove.commands[1];
+        assert_eq!(
+            "ssh #user!@#host! ./deploy.sh #flags! {{version}} && echo done",
+            deploy.command
+        );
+        assert_eq!("echo '{{literal}}'", trove.commands[2].command);
+        assert_eq!("echo '{{literal}}'", trove.commands[2].description);
+
+        assert_eq!(3, warnings.len());
+        assert!(warnings[0].contains("'release'"));
+        assert!(warnings[1].contains("{{version}}"));
+        assert!(warnings[2].contains("'script'"));
+    }
+
+    #[test]
+    fn export_justfile() {
+        let command = HoardCmd::default()
+            .with_name("ping host")
+            .with_namespace("net")
+            .with_command("ping -c #count! #host #host")
+            .with_description("Ping a host");
+        let justfile = trove_to_just(&Trove::from_commands(&[command]), "#", "!");
+        assert_eq!(
+            "# Ping a host\nping_host count host:\n    ping -c {{count}} {{host}} {{host}}\n\n",
+            justfile
+        );
+    }
+
+    #[test]
+    fn just_round_trip() {
+        let (trove, _) = trove_from_just(JUSTFILE, "project", "#", "!");
+        let exported = trove_to_just(&trove, "#", "!");
+        let (reimported, _) = trove_from_just(&exported, "project", "#", "!");
+        assert_eq!(trove.commands, reimported.commands);
+    }
+}
@@ -0,0 +1,224 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::fmt::Write;
+use std::time::SystemTime;
+
+use crate::core::parameters::{split_parameters, CommandPart};
+use crate::core::HoardCmd;
+
+/// Version of the JSON written by `hoard list --format json` and `jsonl`.
+/// It is increased whenever fields are renamed, removed or change their meaning. New fields keep the version
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Columns of `hoard list --format tsv`
+const TSV_HEADER: [&str; 7] = [
+    "name",
+    "namespace",
+    "command",
+    "description",
+    "tags",
+    "usage_count",
+    "last_used",
+];
+
+/// `hoard list --format json`: the schema version and all listed commands
+#[derive(Debug, Serialize)]
+struct CommandList<'a> {
+    version: u32,
+    commands: Vec<CommandRecord<'a>>,
+}
+
+/// A command with all its metadata, as written by `hoard list --format json` and `jsonl`
+///
+/// - `version`: The schema version, only in JSON Lines where there is no enclosing document
+/// - `parameters`: Names of the parameters in the order they appear in the command, empty for unnamed ones
+/// - `created`, `modified`: RFC 3339 timestamps in UTC
+/// - `last_used`: RFC 3339 timestamp in UTC, `null` if the command was never used
+/// - `is_read_only`: The command comes from a subscribed trove
+#[derive(Debug, Serialize)]
+struct CommandRecord<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    name: &'a str,
+    namespace: &'a str,
+    command: &'a str,
+    description: &'a str,
+    tags: &'a [String],
+    parameters: Vec<String>,
+    created: String,
+    modified: String,
+    last_used: Option<String>,
+    usage_count: usize,
+    is_favorite: bool,
+    is_hidden: bool,
+    is_read_only: bool,
+}
+
+impl<'a> CommandRecord<'a> {
+    fn new(command: &'a HoardCmd, parameter_token: &str, parameter_ending_token: &str) -> Self {
+        let parameters =
+            split_parameters(&command.command, parameter_token, parameter_ending_token)
+                .into_iter()
+                .filter_map(|part| match part {
+                    CommandPart::Parameter(name) => Some(name),
+                    CommandPart::Text(_) => None,
+                })
+                .collect();
+        Self {
+            version: None,
+            name: &command.name,
+            namespace: &command.namespace,
+            command: &command.command,
+            description: &command.description,
+            tags: &command.tags,
+            parameters,
+            created: to_rfc3339(command.created),
+            modified: to_rfc3339(command.modified),
+            last_used: (command.usage_count > 0).then(|| to_rfc3339(command.last_used)),
+            usage_count: command.usage_count,
+            is_favorite: command.is_favorite,
+            is_hidden: command.is_hidden,
+            is_read_only: command.is_read_only,
+        }
+    }
+}
+
+/// `time` in UTC, to the second, like `2023-11-14T22:13:20Z`
+pub fn to_rfc3339(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// All commands as one JSON document with the schema version
+pub fn to_json(
+    commands: &[HoardCmd],
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> String {
+    let list = CommandList {
+        version: SCHEMA_VERSION,
+        commands: commands
+            .iter()
+            .map(|c| CommandRecord::new(c, parameter_token, parameter_ending_token))
+            .collect(),
+    };
+    serde_json::to_string_pretty(&list).expect("commands can always be written as JSON")
+}
+
+/// One JSON object per line and command, for streaming into tools like `jq`.
+/// As there is no enclosing document, every object carries the schema version
+pub fn to_json_lines(
+    commands: &[HoardCmd],
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> String {
+    let mut lines = String::new();
+    for command in commands {
+        let record = CommandRecord {
+            version: Some(SCHEMA_VERSION),
+            ..CommandRecord::new(command, parameter_token, parameter_ending_token)
+        };
+        let line = serde_json::to_string(&record).expect("commands can always be written as JSON");
+        let _ = writeln!(lines, "{line}");
+    }
+    lines
+}
+
+/// Escape `\`, tabs and line breaks, so every command stays on one line with one value per column
+fn escape_tsv(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// Tab separated values with a header line
+pub fn to_tsv(commands: &[HoardCmd]) -> String {
+    let mut tsv = TSV_HEADER.join("\t");
+    tsv.push('\n');
+    for command in commands {
+        let last_used = if command.usage_count > 0 {
+            to_rfc3339(command.last_used)
+        } else {
+            String::new()
+        };
+        let row = [
+            escape_tsv(&command.name),
+            escape_tsv(&command.namespace),
+            escape_tsv(&command.command),
+            escape_tsv(&command.description),
+            escape_tsv(&command.get_tags_as_string()),
+            command.usage_count.to_string(),
+            last_used,
+        ];
+        let _ = writeln!(tsv, "{}", row.join("\t"));
+    }
+    tsv
+}
+
+/// Only the names, one per line
+pub fn to_names(commands: &[HoardCmd]) -> String {
+    commands.iter().fold(String::new(), |mut names, command| {
+        let _ = writeln!(names, "{}", command.name);
+        names
+    })
+}
+
+#[cfg(test)]
+mod test_list {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    fn command() -> HoardCmd {
+        let mut command = HoardCmd::default()
+            .with_name("greet")
+            .with_namespace("default")
+            .with_command("echo #greeting! #")
+            .with_description("Say\thello\nto someone")
+            .with_tags_raw("fun,shell");
+        command.created = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        command.modified = command.created;
+        command
+    }
+
+    #[test]
+    fn json_with_all_metadata() {
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&[command()], "#", "!")).unwrap();
+        assert_eq!(SCHEMA_VERSION, json["version"]);
+        let listed = &json["commands"][0];
+        assert_eq!("greet", listed["name"]);
+        assert_eq!(serde_json::json!(["greeting", ""]), listed["parameters"]);
+        assert_eq!(serde_json::json!(["fun", "shell"]), listed["tags"]);
+        assert_eq!("2023-11-14T22:13:20Z", listed["created"]);
+        assert!(listed["last_used"].is_null());
+        assert_eq!(0, listed["usage_count"]);
+        assert_eq!(Some(false), listed["is_favorite"].as_bool());
+        assert!(listed.get("version").is_none());
+    }
+
+    #[test]
+    fn one_json_object_per_line() {
+        let mut used = command().with_name("used");
+        used.mut_record_use();
+        let lines = to_json_lines(&[command(), used], "#", "!");
+        let records: Vec<serde_json::Value> = lines
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(2, records.len());
+        assert_eq!(SCHEMA_VERSION, records[0]["version"]);
+        assert_eq!("used", records[1]["name"]);
+        assert!(records[1]["last_used"].is_string());
+    }
+
+    #[test]
+    fn tsv_keeps_one_row_per_command() {
+        let tsv = to_tsv(&[command()]);
+        let rows: Vec<&str> = tsv.lines().collect();
+        assert_eq!(2, rows.len());
+        assert_eq!(TSV_HEADER.len(), rows[1].split('\t').count());
+        assert!(rows[1].contains("Say\\thello\\nto someone"));
+        assert_eq!("greet\n", to_names(&[command()]));
+    }
+}
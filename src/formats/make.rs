@@ -0,0 +1,258 @@
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::formats::add_with_unique_name;
+
+/// Stands in for an escaped `$$` while variables are replaced
+const ESCAPED_DOLLAR: &str = "\u{0}";
+
+/// A variable assigned in a Makefile
+enum Variable {
+    /// Assigned with `?=`, meant to be overridden like `make deploy ENV=prod`
+    Overridable(String),
+    /// Assigned with `=`, `:=`, `::=` or `+=`
+    Fixed(String),
+}
+
+/// Join lines ending with a `\\` with the next line
+fn join_continued_lines(makefile: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut continued = String::new();
+    for line in makefile.lines() {
+        match line.strip_suffix('\\') {
+            Some(start) => {
+                continued.push_str(start.trim_end());
+                continued.push(' ');
+            }
+            None if continued.is_empty() => lines.push(line.to_string()),
+            None => {
+                continued.push_str(line.trim_start());
+                lines.push(std::mem::take(&mut continued));
+            }
+        }
+    }
+    if !continued.is_empty() {
+        lines.push(continued);
+    }
+    lines
+}
+
+/// A target name hoard can use as command name. Pattern rules and file targets are skipped
+fn is_command_target(target: &str) -> bool {
+    !target.starts_with('.') && !target.contains(['%', '$', '/'])
+}
+
+/// Replace the variables of a recipe line.
+///
+/// Variables assigned with `?=` and variables the Makefile does not assign become hoard parameters,
+/// all other variables are replaced by their value. `$@` becomes the target name
+fn convert_recipe_line(
+    line: &str,
+    target: &str,
+    variables: &HashMap<String, Variable>,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+    warnings: &mut Vec<String>,
+) -> String {
+    let variable = Regex::new(r"\$(?:\(([^()]*)\)|\{([^{}]*)\}|([@<^*?]))").unwrap();
+    let line = line.replace("$$", ESCAPED_DOLLAR);
+    variable
+        .replace_all(&line, |captures: &Captures| {
+            if captures.get(3).is_some_and(|v| v.as_str() == "@") {
+                return target.to_string();
+            }
+            let name = captures
+                .get(1)
+                .or_else(|| captures.get(2))
+                .map_or("", |name| name.as_str());
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                // Automatic variables and function calls like `$(shell ...)`
+                warnings.push(format!(
+                    "Kept {} in target '{target}', only variables are converted",
+                    &captures[0]
+                ));
+                return captures[0].to_string();
+            }
+            match variables.get(name) {
+                Some(Variable::Fixed(value)) => value.clone(),
+                Some(Variable::Overridable(_)) | None => {
+                    format!("{parameter_token}{name}{parameter_ending_token}")
+                }
+            }
+        })
+        .replace(ESCAPED_DOLLAR, "$")
+}
+
+/// Parse a Makefile into a trove. All targets with a recipe are put into `namespace`.
+///
+/// - Comment lines right above a target, or a `## comment` behind it, become its description
+/// - Variables which can be set when running make become hoard parameters. Their default values
+///   are dropped with a warning, as hoard parameters have none
+/// - The lines of a recipe are joined with `&&`, `@` and `-` prefixes are dropped
+/// - Special targets, pattern rules and file targets are skipped
+pub fn trove_from_make(
+    makefile: &str,
+    namespace: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> (Trove, Vec<String>) {
+    let lines = join_continued_lines(makefile);
+    let assignment = Regex::new(
+        r"^(?:export\s+|override\s+)?([A-Za-z_][A-Za-z0-9_.-]*)\s*(\?=|::=|:=|\+=|=)\s*(.*)$",
+    )
+    .unwrap();
+
+    let mut variables = HashMap::new();
+    for line in &lines {
+        if let Some(captures) = assignment.captures(line) {
+            let value = captures[3].trim().to_string();
+            let variable = if &captures[2] == "?=" {
+                Variable::Overridable(value)
+            } else {
+                Variable::Fixed(value)
+            };
+            variables.insert(captures[1].to_string(), variable);
+        }
+    }
+
+    let mut trove = Trove::default();
+    let mut warnings = Vec::new();
+    let mut comments: Vec<&str> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let line = &lines[index];
+        index += 1;
+        if let Some(comment) = line.strip_prefix('#') {
+            comments.push(comment.trim_start_matches('#').trim());
+            continue;
+        }
+        let target_line = if line.starts_with(char::is_whitespace) || assignment.is_match(line) {
+            None
+        } else {
+            line.split_once(':')
+                .filter(|(_, rest)| !rest.starts_with([':', '=']))
+        };
+        let Some((targets, rest)) = target_line else {
+            comments.clear();
+            continue;
+        };
+
+        let mut recipe = Vec::new();
+        while let Some(recipe_line) = lines.get(index).and_then(|l| l.strip_prefix('\t')) {
+            index += 1;
+            let recipe_line = recipe_line
+                .trim()
+                .trim_start_matches(['@', '-', '+'])
+                .trim();
+            if !recipe_line.is_empty() && !recipe_line.starts_with('#') {
+                recipe.push(recipe_line);
+            }
+        }
+
+        let inline_comment = rest.split_once("##").map(|(_, comment)| comment.trim());
+        let description = inline_comment.map_or_else(|| comments.join("\n"), ToString::to_string);
+        comments.clear();
+        if recipe.is_empty() {
+            continue;
+        }
+
+        for target in targets.split_whitespace().filter(|t| is_command_target(t)) {
+            let command = recipe
+                .iter()
+                .map(|line| {
+                    convert_recipe_line(
+                        line,
+                        target,
+                        &variables,
+                        parameter_token,
+                        parameter_ending_token,
+                        &mut warnings,
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(" && ");
+            let description = if description.is_empty() {
+                command.clone()
+            } else {
+                description.clone()
+            };
+            let new_command = HoardCmd::default()
+                .with_name(target)
+                .with_namespace(namespace)
+                .with_command(&command)
+                .with_description(&description);
+            add_with_unique_name(&mut trove, new_command);
+        }
+    }
+
+    for (name, variable) in &variables {
+        let is_used = trove.commands.iter().any(|c| {
+            c.command
+                .contains(&format!("{parameter_token}{name}{parameter_ending_token}"))
+        });
+        if let Variable::Overridable(default) = variable {
+            if is_used && !default.is_empty() {
+                warnings.push(format!(
+                    "Dropped default value '{default}' of variable '{name}'"
+                ));
+            }
+        }
+    }
+    warnings.sort();
+    (trove, warnings)
+}
+
+#[cfg(test)]
+mod test_make {
+    use super::*;
+
+    const MAKEFILE: &str = "\
+ENV ?= staging
+IMAGE := registry.example.com/app
+.PHONY: build deploy clean
+
+# Build the image
+build:
+\t@docker build -t $(IMAGE) .
+
+deploy: build ## Deploy to an environment
+\tkubectl --context $(ENV) apply \\
+\t  -f k8s/
+\t-echo \"deployed $@ to ${ENV} by $(USER)\"
+
+clean:
+\trm -rf $$HOME/.cache/app $(shell pwd)/dist
+
+%.o: %.c
+\tcc -c $<
+
+all: build deploy
+";
+
+    #[test]
+    fn import_makefile() {
+        let (trove, warnings) = trove_from_make(MAKEFILE, "project", "#", "!");
+        let names: Vec<&str> = trove.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["build", "deploy", "clean"], names);
+
+        let build = &trove.commands[0];
+        assert_eq!("docker build -t registry.example.com/app .", build.command);
+        assert_eq!("Build the image", build.description);
+
+        let deploy = &trove.commands[1];
+        assert_eq!(
+            "kubectl --context #ENV! apply -f k8s/ && echo \"deployed deploy to #ENV! by #USER!\"",
+            deploy.command
+        );
+        assert_eq!("Deploy to an environment", deploy.description);
+
+        let clean = &trove.commands[2];
+        assert_eq!("rm -rf $HOME/.cache/app $(shell pwd)/dist", clean.command);
+
+        assert_eq!(2, warnings.len());
+        assert!(warnings[0].contains("'staging'"));
+        assert!(warnings[1].contains("$(shell pwd)"));
+    }
+}
@@ -0,0 +1,147 @@
+pub mod just;
+pub mod list;
+pub mod make;
+pub mod navi;
+pub mod pet;
+pub mod shell;
+pub mod show;
+
+use crate::core::parameters::{split_parameters, CommandPart};
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+
+/// Maximum length of a command name derived from free text
+const MAX_NAME_LENGTH: usize = 40;
+
+/// Turn free text, like a description, into a valid command name.
+/// Whitespace becomes `_`, everything that is not alphanumeric, `_`, `-` or `.` is dropped
+///
+/// # Example
+///
+/// ```
+/// assert_eq!(to_command_name("Change the branch!"), "change_the_branch");
+/// ```
+pub fn to_command_name(text: &str) -> String {
+    let name = text
+        .split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                .collect::<String>()
+                .to_lowercase()
+        })
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+    let name: String = name.chars().take(MAX_NAME_LENGTH).collect();
+    let name = name.trim_end_matches('_').to_string();
+    if name.is_empty() {
+        String::from("command")
+    } else {
+        name
+    }
+}
+
+/// Add an imported command to `trove`, numbering its name if another command in the namespace already uses it.
+/// Commands which are already present in the trove are skipped
+pub fn add_with_unique_name(trove: &mut Trove, command: HoardCmd) {
+    let base_name = command.name.clone();
+    let mut command = command;
+    let mut suffix = 1;
+    while let Some(collision) = trove.get_command_collision(&command) {
+        if collision.command == command.command {
+            return;
+        }
+        suffix += 1;
+        command.name = format!("{base_name}_{suffix}");
+    }
+    let _ = trove.add_command(command, false);
+}
+
+/// Move all commands of `trove` into `namespace`, numbering names that collide in the new namespace
+pub fn into_namespace(trove: &Trove, namespace: &str) -> Trove {
+    let mut moved = Trove::default();
+    for command in &trove.commands {
+        add_with_unique_name(&mut moved, command.clone().with_namespace(namespace));
+    }
+    moved
+}
+
+/// Replace the hoard parameters of `command` with the placeholder syntax of another tool.
+///
+/// `placeholder` receives the parameter name with whitespace replaced by `_`.
+/// Unnamed parameters are called `param1`, `param2`, ...
+pub fn map_parameters<F>(
+    command: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+    mut placeholder: F,
+) -> String
+where
+    F: FnMut(&str) -> String,
+{
+    let mut unnamed_count = 0;
+    split_parameters(command, parameter_token, parameter_ending_token)
+        .into_iter()
+        .map(|part| match part {
+            CommandPart::Text(text) => text,
+            CommandPart::Parameter(name) if name.trim().is_empty() => {
+                unnamed_count += 1;
+                placeholder(&format!("param{unnamed_count}"))
+            }
+            CommandPart::Parameter(name) => {
+                placeholder(&name.split_whitespace().collect::<Vec<_>>().join("_"))
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_formats {
+    use super::*;
+
+    #[test]
+    fn command_name_from_description() {
+        assert_eq!("change_the_branch", to_command_name("Change the  branch!"));
+    }
+
+    #[test]
+    fn command_name_from_empty_text() {
+        assert_eq!("command", to_command_name(" ?! "));
+    }
+
+    #[test]
+    fn map_named_and_unnamed_parameters() {
+        let mapped = map_parameters("cp # #target dir! #", "#", "!", |name| {
+            format!("{{{{{name}}}}}")
+        });
+        assert_eq!("cp {{param1}} {{target_dir}} {{param2}}", mapped);
+    }
+
+    #[test]
+    fn move_into_namespace() {
+        let first = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status");
+        let second = first.clone().with_namespace("jj").with_command("jj status");
+        let moved = into_namespace(&Trove::from_commands(&[first, second]), "vcs");
+        let names: Vec<&str> = moved.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["status", "status_2"], names);
+        assert_eq!(vec!["vcs"], moved.namespaces());
+    }
+
+    #[test]
+    fn numbered_names_on_collision() {
+        let mut trove = Trove::default();
+        let command = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status");
+        add_with_unique_name(&mut trove, command.clone());
+        add_with_unique_name(&mut trove, command.clone());
+        add_with_unique_name(&mut trove, command.with_command("git status -s"));
+        let names: Vec<&str> = trove.commands.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(vec!["status", "status_2"], names);
+    }
+}
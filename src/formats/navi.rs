@@ -0,0 +1,272 @@
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::formats::{add_with_unique_name, map_parameters, to_command_name};
+
+/// Prefix of a navi value source (`$ branch: git branch`).
+/// Value sources are kept as lines of the description, as hoard has no equivalent
+const VALUE_SOURCE_PREFIX: &str = "$ ";
+
+/// A cheat section, everything below a `% tags` line
+#[derive(Default)]
+struct Section {
+    tags: Vec<String>,
+    commands: Vec<(String, String)>,
+    value_sources: Vec<(String, String)>,
+}
+
+/// Parse a navi cheatsheet into a trove.
+///
+/// - `% tags`: The first tag becomes the namespace, the others tags. Without tags, `default_namespace` is used
+/// - `# description`: Description of the next command, its name is derived from it.
+///   Without a description, the command itself is used
+/// - `<var>`: Turned into a hoard parameter `#var!`
+/// - `$ var: command`: Appended to the description of every command using `<var>`
+pub fn trove_from_navi(
+    cheat: &str,
+    default_namespace: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> Trove {
+    let mut sections: Vec<Section> = vec![Section::default()];
+    let mut description = String::new();
+    let mut command: Option<String> = None;
+
+    for line in cheat.lines() {
+        let trimmed = line.trim();
+        if trimmed.starts_with('%') || trimmed.is_empty() || trimmed.starts_with('#') {
+            let section = sections.last_mut().expect("there is always a section");
+            if let Some(c) = command.take() {
+                section.commands.push((std::mem::take(&mut description), c));
+            }
+        }
+
+        if let Some(tags) = trimmed.strip_prefix('%') {
+            sections.push(Section {
+                tags: tags
+                    .split(',')
+                    .map(|t| t.split_whitespace().collect::<Vec<_>>().join("_"))
+                    .filter(|t| !t.is_empty())
+                    .collect(),
+                ..Section::default()
+            });
+        } else if let Some(d) = trimmed.strip_prefix('#') {
+            if !description.is_empty() {
+                description.push(' ');
+            }
+            description.push_str(d.trim());
+        } else if let Some(value_source) = trimmed.strip_prefix('$') {
+            if let Some((var, source)) = value_source.split_once(':') {
+                sections
+                    .last_mut()
+                    .expect("there is always a section")
+                    .value_sources
+                    .push((var.trim().to_string(), source.trim().to_string()));
+            }
+        } else if trimmed.is_empty()
+            || trimmed.starts_with(';')
+            || trimmed.starts_with('@')
+            || trimmed.starts_with("```")
+        {
+            // Comments, extended cheats and code fences have no meaning in hoard
+        } else {
+            match command.as_mut() {
+                Some(c) => {
+                    c.push('\n');
+                    c.push_str(line.trim_end());
+                }
+                None => command = Some(line.trim_end().to_string()),
+            }
+        }
+    }
+    if let Some(c) = command.take() {
+        sections
+            .last_mut()
+            .expect("there is always a section")
+            .commands
+            .push((description, c));
+    }
+
+    let variable = Regex::new(r"<([\w-]+)>").unwrap();
+    let mut trove = Trove::default();
+    for section in sections {
+        let namespace = section
+            .tags
+            .first()
+            .map_or(default_namespace, String::as_str);
+        for (description, command) in section.commands {
+            // navi lists snippets without a description by their command
+            let description = if description.is_empty() {
+                command
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            } else {
+                description
+            };
+            let mut full_description = description.clone();
+            for (var, source) in &section.value_sources {
+                if command.contains(&format!("<{var}>")) {
+                    let _ = write!(full_description, "\n{VALUE_SOURCE_PREFIX}{var}: {source}");
+                }
+            }
+            let name = to_command_name(&description);
+            let hoard_command = variable.replace_all(
+                &command,
+                format!("{parameter_token}${{1}}{parameter_ending_token}").as_str(),
+            );
+            let new_command = HoardCmd::default()
+                .with_name(&name)
+                .with_namespace(namespace)
+                .with_command(&hoard_command)
+                .with_description(full_description.trim())
+                .with_tags(section.tags.iter().skip(1).cloned().collect());
+            add_with_unique_name(&mut trove, new_command);
+        }
+    }
+    trove
+}
+
+/// Write a trove as a navi cheatsheet.
+/// Commands are grouped by namespace and tags into `% namespace, tag1, tag2` sections
+pub fn trove_to_navi(trove: &Trove, parameter_token: &str, parameter_ending_token: &str) -> String {
+    let mut sections: BTreeMap<Vec<&str>, Vec<&HoardCmd>> = BTreeMap::new();
+    for command in &trove.commands {
+        let mut header = vec![command.namespace.as_str()];
+        header.extend(command.tags.iter().map(String::as_str));
+        sections.entry(header).or_default().push(command);
+    }
+
+    let mut cheat = String::new();
+    for (header, commands) in sections {
+        let _ = writeln!(cheat, "% {}\n", header.join(", "));
+        let mut value_sources: Vec<&str> = Vec::new();
+        for command in commands {
+            let mut has_description = false;
+            for line in command.description.lines().filter(|l| !l.trim().is_empty()) {
+                if let Some(value_source) = line.strip_prefix(VALUE_SOURCE_PREFIX) {
+                    if !value_sources.contains(&value_source) {
+                        value_sources.push(value_source);
+                    }
+                } else {
+                    has_description = true;
+                    let _ = writeln!(cheat, "# {}", line.trim());
+                }
+            }
+            if !has_description {
+                let _ = writeln!(cheat, "# {}", command.name);
+            }
+            let _ = writeln!(
+                cheat,
+                "{}\n",
+                map_parameters(
+                    &command.command,
+                    parameter_token,
+                    parameter_ending_token,
+                    |name| format!("<{name}>")
+                )
+            );
+        }
+        for value_source in value_sources {
+            let _ = writeln!(cheat, "{VALUE_SOURCE_PREFIX}{value_source}");
+        }
+        if !cheat.ends_with("\n\n") {
+            cheat.push('\n');
+        }
+    }
+    cheat
+}
+
+#[cfg(test)]
+mod test_navi {
+    use super::*;
+
+    const CHEAT: &str = "\
+% git, code
+
+# Change branch
+git checkout <branch>
+
+# Show log
+; a comment
+git log \\
+  --oneline
+
+$ branch: git branch | awk '{print $NF}'
+
+% docker
+
+docker ps -a
+";
+
+    #[test]
+    fn import_navi_cheat() {
+        let trove = trove_from_navi(CHEAT, "default", "#", "!");
+        assert_eq!(3, trove.commands.len());
+
+        let checkout = &trove.commands[0];
+        assert_eq!("change_branch", checkout.name);
+        assert_eq!("git", checkout.namespace);
+        assert_eq!("git checkout #branch!", checkout.command);
+        assert_eq!(vec!["code".to_string()], checkout.tags);
+        assert_eq!(
+            "Change branch\n$ branch: git branch | awk '{print $NF}'",
+            checkout.description
+        );
+
+        let log = &trove.commands[1];
+        assert_eq!("show_log", log.name);
+        assert_eq!("git log \\\n  --oneline", log.command);
+        assert_eq!("Show log", log.description);
+
+        let ps = &trove.commands[2];
+        assert_eq!("docker", ps.namespace);
+        assert_eq!("docker_ps_-a", ps.name);
+        assert_eq!("docker ps -a", ps.description);
+        assert!(ps.tags.is_empty());
+    }
+
+    #[test]
+    fn import_navi_cheat_without_tags() {
+        let trove = trove_from_navi("# Say hello\necho hello <name>\n", "default", "@", "$");
+        assert_eq!("default", trove.commands[0].namespace);
+        assert_eq!("echo hello @name$", trove.commands[0].command);
+    }
+
+    #[test]
+    fn export_navi_cheat() {
+        let command = HoardCmd::default()
+            .with_name("greet")
+            .with_namespace("misc")
+            .with_command("echo #greeting! #first name! #")
+            .with_description("Greet someone\n$ greeting: echo hi")
+            .with_tags(vec!["fun".to_string()]);
+        let trove = Trove::from_commands(&[command]);
+        let expected = "\
+% misc, fun
+
+# Greet someone
+echo <greeting> <first_name> <param1>
+
+$ greeting: echo hi
+
+";
+        assert_eq!(expected, trove_to_navi(&trove, "#", "!"));
+    }
+
+    #[test]
+    fn navi_round_trip() {
+        let trove = trove_from_navi(CHEAT, "default", "#", "!");
+        let exported = trove_to_navi(&trove, "#", "!");
+        let mut reimported = trove_from_navi(&exported, "default", "#", "!").commands;
+        reimported.sort_by(|a, b| a.name.cmp(&b.name));
+        let mut commands = trove.commands;
+        commands.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(commands, reimported);
+    }
+}
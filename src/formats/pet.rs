@@ -0,0 +1,179 @@
+use anyhow::Result;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::formats::{add_with_unique_name, map_parameters, to_command_name};
+
+/// Separates the description from the sample output of a pet snippet, as hoard has no field for it
+const OUTPUT_SEPARATOR: &str = "\n\nOutput:\n";
+
+/// Layout of pet's `snippet.toml`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PetSnippets {
+    #[serde(default)]
+    snippets: Vec<PetSnippet>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PetSnippet {
+    #[serde(default)]
+    description: String,
+    command: String,
+    #[serde(default)]
+    tag: Vec<String>,
+    #[serde(default)]
+    output: String,
+}
+
+/// Parse pet's `snippet.toml` into a trove. All snippets are put into `namespace`.
+///
+/// - `description`: Description of the command, its name is derived from it
+/// - `<param>`: Turned into a hoard parameter `#param!`
+/// - `<param=default>`: hoard parameters have no default values. They are dropped,
+///   and a warning for each dropped default is returned alongside the trove
+/// - `output`: Appended to the description
+pub fn trove_from_pet(
+    snippet_toml: &str,
+    namespace: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> Result<(Trove, Vec<String>)> {
+    let pet_snippets: PetSnippets = toml::from_str(snippet_toml)?;
+    let parameter = Regex::new(r"<([^<>=\s]+)(?:=([^<>]*))?>").unwrap();
+    let mut warnings = Vec::new();
+    let mut trove = Trove::default();
+
+    for snippet in pet_snippets.snippets {
+        for captures in parameter.captures_iter(&snippet.command) {
+            if let Some(default) = captures.get(2).filter(|d| !d.as_str().is_empty()) {
+                warnings.push(format!(
+                    "Dropped default value '{}' of parameter '{}' in: {}",
+                    default.as_str(),
+                    &captures[1],
+                    snippet.command
+                ));
+            }
+        }
+        let command = parameter.replace_all(
+            &snippet.command,
+            format!("{parameter_token}${{1}}{parameter_ending_token}").as_str(),
+        );
+
+        let mut description = if snippet.description.trim().is_empty() {
+            snippet.command.clone()
+        } else {
+            snippet.description.trim().to_string()
+        };
+        let name = to_command_name(&description);
+        if !snippet.output.trim().is_empty() {
+            description.push_str(OUTPUT_SEPARATOR);
+            description.push_str(snippet.output.trim_end());
+        }
+
+        let tags = snippet
+            .tag
+            .iter()
+            .map(|t| t.split_whitespace().collect::<Vec<_>>().join("_"))
+            .filter(|t| !t.is_empty())
+            .collect();
+        let new_command = HoardCmd::default()
+            .with_name(&name)
+            .with_namespace(namespace)
+            .with_command(&command)
+            .with_description(&description)
+            .with_tags(tags);
+        add_with_unique_name(&mut trove, new_command);
+    }
+    Ok((trove, warnings))
+}
+
+/// Write a trove as pet's `snippet.toml`
+pub fn trove_to_pet(
+    trove: &Trove,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> Result<String> {
+    let snippets = trove
+        .commands
+        .iter()
+        .map(|command| {
+            let (description, output) = command
+                .description
+                .split_once(OUTPUT_SEPARATOR)
+                .unwrap_or((command.description.as_str(), ""));
+            PetSnippet {
+                description: description.to_string(),
+                command: map_parameters(
+                    &command.command,
+                    parameter_token,
+                    parameter_ending_token,
+                    |name| format!("<{name}>"),
+                ),
+                tag: command.tags.clone(),
+                output: output.to_string(),
+            }
+        })
+        .collect();
+    Ok(toml::to_string(&PetSnippets { snippets })?)
+}
+
+#[cfg(test)]
+mod test_pet {
+    use super::*;
+
+    const SNIPPETS: &str = r#"
+[[snippets]]
+  description = "Ping a host"
+  command = "ping -c <count=3> <host>"
+  tag = ["network", "ops tools"]
+  output = "64 bytes from 8.8.8.8"
+
+[[snippets]]
+  command = "docker ps -a"
+"#;
+
+    #[test]
+    fn import_pet_snippets() {
+        let (trove, warnings) = trove_from_pet(SNIPPETS, "pet", "#", "!").unwrap();
+        assert_eq!(2, trove.commands.len());
+
+        let ping = &trove.commands[0];
+        assert_eq!("ping_a_host", ping.name);
+        assert_eq!("pet", ping.namespace);
+        assert_eq!("ping -c #count! #host!", ping.command);
+        assert_eq!(
+            vec!["network".to_string(), "ops_tools".to_string()],
+            ping.tags
+        );
+        assert_eq!(
+            "Ping a host\n\nOutput:\n64 bytes from 8.8.8.8",
+            ping.description
+        );
+        assert_eq!(1, warnings.len());
+        assert!(warnings[0].contains("'3'"));
+
+        let docker = &trove.commands[1];
+        assert_eq!("docker_ps_-a", docker.name);
+        assert_eq!("docker ps -a", docker.description);
+        assert!(docker.tags.is_empty());
+    }
+
+    #[test]
+    fn import_invalid_pet_snippets() {
+        assert!(trove_from_pet("[[snippets]]\ndescription = 1", "pet", "#", "!").is_err());
+    }
+
+    #[test]
+    fn pet_round_trip() {
+        let (trove, _) = trove_from_pet(SNIPPETS, "pet", "#", "!").unwrap();
+        let exported = trove_to_pet(&trove, "#", "!").unwrap();
+        assert!(exported.contains("command = \"ping -c <count> <host>\""));
+        assert!(exported.contains("output = \"64 bytes from 8.8.8.8\""));
+
+        let (reimported, warnings) = trove_from_pet(&exported, "pet", "#", "!").unwrap();
+        assert!(warnings.is_empty());
+        assert_eq!(trove.commands, reimported.commands);
+    }
+}
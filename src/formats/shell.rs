@@ -0,0 +1,372 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use crate::cli_commands::Shell;
+use crate::core::parameters::{split_parameters, CommandPart};
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+
+/// Builtins and reserved words of bash. Functions with these names would shadow them
+const BASH_BUILTINS: &str = "\
+    alias bg bind break builtin caller case cd command compgen complete compopt continue \
+    coproc declare dirs disown do done echo elif else enable esac eval exec exit export \
+    false fc fg fi for function getopts hash help history if in jobs kill let local logout \
+    mapfile popd printf pushd pwd read readarray readonly return select set shift shopt \
+    source suspend test then time times trap true type typeset ulimit umask unalias unset \
+    until wait while";
+
+/// Builtins and reserved words of zsh on top of the ones shared with bash
+const ZSH_BUILTINS: &str = "\
+    autoload bindkey bye chdir emulate float foreach functions integer limit noglob print \
+    pushln r rehash repeat sched setopt unfunction unlimit unsetopt vared whence where which \
+    zcompile zle zmodload zparseopts zstyle";
+
+/// Builtins and reserved words of fish
+const FISH_BUILTINS: &str = "\
+    abbr and argparse begin bg bind block break breakpoint builtin case cd command \
+    commandline complete contains continue count dirh dirs disown echo else emit end eval \
+    exec exit false fg for function functions history if jobs math not or printf pwd random \
+    read realpath return set set_color source status string switch test time true type \
+    ulimit wait while";
+
+fn is_builtin(shell: Shell, name: &str) -> bool {
+    let builtins = match shell {
+        Shell::Bash => BASH_BUILTINS.to_string(),
+        Shell::Zsh => format!("{BASH_BUILTINS} {ZSH_BUILTINS}"),
+        Shell::Fish => FISH_BUILTINS.to_string(),
+    };
+    builtins.split_whitespace().any(|builtin| builtin == name)
+}
+
+/// Turn a command name into a valid function name.
+/// Everything that is not alphanumeric, `_` or `-` becomes `_`, and a name never starts with a digit or `-`
+fn to_function_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = name.trim_start_matches('-');
+    match name.chars().next() {
+        None => String::from("command"),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name.to_string(),
+    }
+}
+
+/// Pick a function name for `command` which neither shadows a builtin nor is used by another function.
+/// Falls back to `<namespace>_<name>`, then numbers the name
+fn unique_function_name(shell: Shell, command: &HoardCmd, used_names: &HashSet<String>) -> String {
+    let is_free = |name: &str| !is_builtin(shell, name) && !used_names.contains(name);
+    let name = to_function_name(&command.name);
+    if is_free(&name) {
+        return name;
+    }
+    let name = to_function_name(&format!("{}_{}", command.namespace, command.name));
+    let mut candidate = name.clone();
+    let mut suffix = 1;
+    while !is_free(&candidate) {
+        suffix += 1;
+        candidate = format!("{name}_{suffix}");
+    }
+    candidate
+}
+
+/// Quote `text` as a single shell word which is not expanded
+fn quote(shell: Shell, text: &str) -> String {
+    match shell {
+        Shell::Bash | Shell::Zsh => format!("'{}'", text.replace('\'', r"'\''")),
+        Shell::Fish => format!("'{}'", text.replace('\\', r"\\").replace('\'', r"\'")),
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    None,
+    Single,
+    Double,
+}
+
+/// Quoting in effect at the end of `text`, when it starts with `quoting`
+fn quoting_after(text: &str, mut quoting: Quoting) -> Quoting {
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        quoting = match (quoting, c) {
+            (Quoting::None | Quoting::Double, '\\') => {
+                chars.next();
+                quoting
+            }
+            (Quoting::None, '\'') => Quoting::Single,
+            (Quoting::None, '"') => Quoting::Double,
+            (Quoting::Single, '\'') | (Quoting::Double, '"') => Quoting::None,
+            _ => quoting,
+        };
+    }
+    quoting
+}
+
+/// Reference to the positional argument `position` which expands to exactly one word inside `quoting`
+fn positional_argument(shell: Shell, position: usize, quoting: Quoting) -> String {
+    let argument = match shell {
+        Shell::Bash | Shell::Zsh => format!("${{{position}}}"),
+        Shell::Fish => format!("$argv[{position}]"),
+    };
+    match quoting {
+        Quoting::None => format!("\"{argument}\""),
+        Quoting::Single => format!("'\"{argument}\"'"),
+        Quoting::Double => argument,
+    }
+}
+
+/// Replace the hoard parameters of `command` with positional arguments.
+/// Named parameters use the same argument for every occurrence, every unnamed parameter gets its own.
+/// Returns the new command and the names of its arguments
+fn with_positional_arguments(
+    shell: Shell,
+    command: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> (String, Vec<String>) {
+    let mut arguments: Vec<String> = Vec::new();
+    let mut body = String::new();
+    let mut quoting = Quoting::None;
+    for part in split_parameters(command, parameter_token, parameter_ending_token) {
+        match part {
+            CommandPart::Text(text) => {
+                quoting = quoting_after(&text, quoting);
+                body.push_str(&text);
+            }
+            CommandPart::Parameter(name) => {
+                let name = name.split_whitespace().collect::<Vec<_>>().join("_");
+                let position = if name.is_empty() {
+                    arguments.push(format!("param{}", arguments.len() + 1));
+                    arguments.len()
+                } else if let Some(index) = arguments.iter().position(|a| a == &name) {
+                    index + 1
+                } else {
+                    arguments.push(name);
+                    arguments.len()
+                };
+                body.push_str(&positional_argument(shell, position, quoting));
+            }
+        }
+    }
+    (body, arguments)
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn to_function(
+    shell: Shell,
+    command: &HoardCmd,
+    name: &str,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> String {
+    let (body, arguments) = with_positional_arguments(
+        shell,
+        &command.command,
+        parameter_token,
+        parameter_ending_token,
+    );
+    let mut usage = format!("Usage: {name}");
+    for argument in &arguments {
+        let _ = write!(usage, " <{argument}>");
+    }
+    let mut usage_lines = vec![usage];
+    usage_lines.extend(command.description.lines().map(ToString::to_string));
+    let echo_usage = usage_lines
+        .iter()
+        .map(|line| format!("echo {} >&2", quote(shell, line)))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut function = String::new();
+    for line in command.description.lines() {
+        let _ = writeln!(function, "# {line}");
+    }
+    match shell {
+        Shell::Bash | Shell::Zsh => {
+            let _ = writeln!(function, "{name}() {{");
+            if !arguments.is_empty() {
+                let _ = writeln!(
+                    function,
+                    "    if [ \"$#\" -lt {} ]; then\n{}\n        return 1\n    fi",
+                    arguments.len(),
+                    indent(&indent(&echo_usage))
+                );
+            }
+            let _ = writeln!(function, "{}\n}}", indent(&body));
+        }
+        Shell::Fish => {
+            let description = command.description.lines().next().unwrap_or_default();
+            let _ = writeln!(
+                function,
+                "function {name} --description {}",
+                quote(shell, description)
+            );
+            if !arguments.is_empty() {
+                let _ = writeln!(
+                    function,
+                    "    if test (count $argv) -lt {}\n{}\n        return 1\n    end",
+                    arguments.len(),
+                    indent(&indent(&echo_usage))
+                );
+            }
+            let _ = writeln!(function, "{}\nend", indent(&body));
+        }
+    }
+    function
+}
+
+/// Write every command of a trove as a shell function named after the command, ready to be sourced.
+///
+/// - Parameters become positional arguments, which are checked against a usage text built from the description
+/// - Names are made valid function names. Names shadowing a shell builtin, a program run by one of the commands
+///   or used by a command in another namespace are prefixed with the namespace instead, so `ls() { ls -la; }`
+///   never calls itself. A warning is returned for every renamed function
+pub fn trove_to_shell_functions(
+    trove: &Trove,
+    shell: Shell,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> (String, Vec<String>) {
+    let mut used_names: HashSet<String> = trove
+        .commands
+        .iter()
+        .filter_map(|command| command.command.split_whitespace().next())
+        .map(ToString::to_string)
+        .collect();
+    let mut warnings = Vec::new();
+    let mut functions = vec![String::from(
+        "# Generated by hoard. Source this file in your shell config to use your commands as functions\n",
+    )];
+    for command in &trove.commands {
+        let name = unique_function_name(shell, command, &used_names);
+        if name != command.name {
+            warnings.push(format!(
+                "Exported [{}/{}] as function `{name}`",
+                command.namespace, command.name
+            ));
+        }
+        functions.push(to_function(
+            shell,
+            command,
+            &name,
+            parameter_token,
+            parameter_ending_token,
+        ));
+        used_names.insert(name);
+    }
+    (functions.join("\n"), warnings)
+}
+
+#[cfg(test)]
+mod test_shell {
+    use super::*;
+
+    fn command(name: &str, namespace: &str, command: &str) -> HoardCmd {
+        HoardCmd::default()
+            .with_name(name)
+            .with_namespace(namespace)
+            .with_command(command)
+            .with_description("Ping a host")
+    }
+
+    #[test]
+    fn parameters_become_positional_arguments() {
+        let (body, arguments) =
+            with_positional_arguments(Shell::Bash, "ping -c #count! #host #host", "#", "!");
+        assert_eq!(r#"ping -c "${1}" "${2}" "${2}""#, body);
+        assert_eq!(vec!["count", "host"], arguments);
+
+        let (body, arguments) = with_positional_arguments(Shell::Fish, "cp # #", "#", "!");
+        assert_eq!(r#"cp "$argv[1]" "$argv[2]""#, body);
+        assert_eq!(vec!["param1", "param2"], arguments);
+    }
+
+    #[test]
+    fn arguments_respect_quoting() {
+        let (body, _) =
+            with_positional_arguments(Shell::Bash, r#"echo "Hi #name!" 'to #city!'"#, "#", "!");
+        assert_eq!(r#"echo "Hi ${1}" 'to '"${2}"''"#, body);
+    }
+
+    #[test]
+    fn function_names_avoid_builtins_and_collisions() {
+        let trove = Trove::from_commands(&[
+            command("cd", "nav", "cd #dir"),
+            command("1st try", "git", "git log"),
+            command("log", "git", "git log"),
+            command("log", "docker", "docker logs"),
+        ]);
+        let (functions, warnings) = trove_to_shell_functions(&trove, Shell::Bash, "#", "!");
+        assert!(functions.contains("nav_cd() {"));
+        assert!(functions.contains("_1st_try() {"));
+        assert!(functions.contains("\nlog() {"));
+        assert!(functions.contains("docker_log() {"));
+        assert_eq!(3, warnings.len());
+    }
+
+    #[test]
+    fn function_names_avoid_the_programs_they_run() {
+        let trove = Trove::from_commands(&[
+            command("ls", "files", "ls -la"),
+            command("git", "git", "git status"),
+            command("ll", "files", "ls -l"),
+        ]);
+        for shell in [Shell::Bash, Shell::Fish] {
+            let (functions, warnings) = trove_to_shell_functions(&trove, shell, "#", "!");
+            assert!(functions.contains("files_ls"));
+            assert!(functions.contains("git_git"));
+            assert!(!functions.contains("\nls()") && !functions.contains("function ls "));
+            assert_eq!(2, warnings.len());
+        }
+    }
+
+    #[test]
+    fn bash_function_with_usage() {
+        let trove = Trove::from_commands(&[command("ping_host", "net", "ping #host")]);
+        let (functions, _) = trove_to_shell_functions(&trove, Shell::Zsh, "#", "!");
+        assert!(functions.ends_with(
+            r#"# Ping a host
+ping_host() {
+    if [ "$#" -lt 1 ]; then
+        echo 'Usage: ping_host <host>' >&2
+        echo 'Ping a host' >&2
+        return 1
+    fi
+    ping "${1}"
+}
+"#
+        ));
+    }
+
+    #[test]
+    fn fish_function_with_usage() {
+        let trove = Trove::from_commands(&[command("ping_host", "net", "ping #host")]);
+        let (functions, _) = trove_to_shell_functions(&trove, Shell::Fish, "#", "!");
+        assert!(functions.ends_with(
+            r#"# Ping a host
+function ping_host --description 'Ping a host'
+    if test (count $argv) -lt 1
+        echo 'Usage: ping_host <host>' >&2
+        echo 'Ping a host' >&2
+        return 1
+    end
+    ping "$argv[1]"
+end
+"#
+        ));
+    }
+}
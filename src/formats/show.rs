@@ -0,0 +1,208 @@
+use chrono::{DateTime, Local, SecondsFormat};
+use serde::Serialize;
+use std::fmt::Write;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::core::parameters::{split_parameters, CommandPart};
+use crate::core::HoardCmd;
+use crate::formats::list::SCHEMA_VERSION;
+
+/// Local time as shown to humans
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M";
+
+/// Trove file a command is stored in
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Source {
+    /// Your own trove file
+    Trove { path: PathBuf },
+    /// The trove of a subscription, the command is read-only
+    Subscription { uri: String },
+}
+
+/// A parameter of a command, in the order of appearance.
+/// hoard parameters are free text without a type or default value, so only the name is known
+#[derive(Debug, Serialize)]
+struct Parameter {
+    position: usize,
+    name: Option<String>,
+}
+
+/// `hoard show --json`: a command with all its metadata
+///
+/// - `created`, `modified`: RFC 3339 timestamps in local time
+/// - `last_used`: RFC 3339 timestamp in local time, `null` if the command was never used
+/// - `parameters`: `name` is `null` for unnamed parameters
+#[derive(Debug, Serialize)]
+struct CommandDetails<'a> {
+    version: u32,
+    name: &'a str,
+    namespace: &'a str,
+    command: &'a str,
+    description: &'a str,
+    tags: &'a [String],
+    parameters: Vec<Parameter>,
+    created: String,
+    modified: String,
+    last_used: Option<String>,
+    usage_count: usize,
+    is_favorite: bool,
+    is_hidden: bool,
+    is_read_only: bool,
+    source: &'a Source,
+}
+
+fn parameters(
+    command: &HoardCmd,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> Vec<Parameter> {
+    split_parameters(&command.command, parameter_token, parameter_ending_token)
+        .into_iter()
+        .filter_map(|part| match part {
+            CommandPart::Parameter(name) => Some(name),
+            CommandPart::Text(_) => None,
+        })
+        .enumerate()
+        .map(|(i, name)| Parameter {
+            position: i + 1,
+            name: (!name.trim().is_empty()).then_some(name),
+        })
+        .collect()
+}
+
+fn local_time(time: SystemTime) -> DateTime<Local> {
+    DateTime::<Local>::from(time)
+}
+
+/// All metadata of `command` as a JSON document
+pub fn to_json(
+    command: &HoardCmd,
+    source: &Source,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> String {
+    let rfc3339 = |time| local_time(time).to_rfc3339_opts(SecondsFormat::Secs, false);
+    let details = CommandDetails {
+        version: SCHEMA_VERSION,
+        name: &command.name,
+        namespace: &command.namespace,
+        command: &command.command,
+        description: &command.description,
+        tags: &command.tags,
+        parameters: parameters(command, parameter_token, parameter_ending_token),
+        created: rfc3339(command.created),
+        modified: rfc3339(command.modified),
+        last_used: (command.usage_count > 0).then(|| rfc3339(command.last_used)),
+        usage_count: command.usage_count,
+        is_favorite: command.is_favorite,
+        is_hidden: command.is_hidden,
+        is_read_only: command.is_read_only,
+        source,
+    };
+    serde_json::to_string_pretty(&details).expect("a command can always be written as JSON")
+}
+
+/// All metadata of `command`, one field per line
+pub fn to_text(
+    command: &HoardCmd,
+    source: &Source,
+    parameter_token: &str,
+    parameter_ending_token: &str,
+) -> String {
+    let yes_no = |flag: bool| if flag { "yes" } else { "no" };
+    let or_none = |text: &str| {
+        if text.trim().is_empty() {
+            String::from("-")
+        } else {
+            text.to_string()
+        }
+    };
+    let last_used = if command.usage_count > 0 {
+        local_time(command.last_used)
+            .format(TIME_FORMAT)
+            .to_string()
+    } else {
+        String::from("never")
+    };
+    let source = match source {
+        Source::Trove { path } => path.display().to_string(),
+        Source::Subscription { uri } => format!("{uri} (subscription, read-only)"),
+    };
+
+    let mut text = String::new();
+    let mut field = |label: &str, value: &str| {
+        let _ = writeln!(text, "{label:<12} {value}");
+    };
+    field("Name", &command.name);
+    field("Namespace", &command.namespace);
+    field("Command", &command.command);
+    field("Description", &or_none(&command.description));
+    field("Tags", &or_none(&command.get_tags_as_string()));
+    field(
+        "Created",
+        &local_time(command.created).format(TIME_FORMAT).to_string(),
+    );
+    field(
+        "Modified",
+        &local_time(command.modified).format(TIME_FORMAT).to_string(),
+    );
+    field("Last used", &last_used);
+    field("Times used", &command.usage_count.to_string());
+    field("Favorite", yes_no(command.is_favorite));
+    field("Hidden", yes_no(command.is_hidden));
+    field("Trove", &source);
+
+    let parameters = parameters(command, parameter_token, parameter_ending_token);
+    if parameters.is_empty() {
+        field("Parameters", "-");
+    }
+    for (i, parameter) in parameters.iter().enumerate() {
+        let label = if i == 0 { "Parameters" } else { "" };
+        let name = parameter.name.as_deref().unwrap_or("(unnamed)");
+        field(label, &format!("{}. {name}", parameter.position));
+    }
+    text
+}
+
+#[cfg(test)]
+mod test_show {
+    use super::*;
+
+    fn command() -> HoardCmd {
+        HoardCmd::default()
+            .with_name("copy")
+            .with_namespace("files")
+            .with_command("cp # #target dir!")
+            .with_tags_raw("fs")
+    }
+
+    fn source() -> Source {
+        Source::Subscription {
+            uri: String::from("https://example.com/team.yml"),
+        }
+    }
+
+    #[test]
+    fn text_with_all_fields() {
+        let text = to_text(&command(), &source(), "#", "!");
+        assert!(text.contains("Namespace    files\n"));
+        assert!(text.contains("Description  -\n"));
+        assert!(text.contains("Last used    never\n"));
+        assert!(text.contains("Parameters   1. (unnamed)\n             2. target dir\n"));
+        assert!(text.contains("https://example.com/team.yml (subscription, read-only)"));
+    }
+
+    #[test]
+    fn json_with_parameters_and_source() {
+        let json: serde_json::Value =
+            serde_json::from_str(&to_json(&command(), &source(), "#", "!")).unwrap();
+        assert_eq!(SCHEMA_VERSION, json["version"]);
+        assert!(json["parameters"][0]["name"].is_null());
+        assert_eq!("target dir", json["parameters"][1]["name"]);
+        assert_eq!(2, json["parameters"][1]["position"]);
+        assert_eq!("subscription", json["source"]["type"]);
+        assert!(json["last_used"].is_null());
+    }
+}
@@ -0,0 +1,374 @@
+/// Score of every matched character
+const SCORE_MATCH: i64 = 16;
+/// Penalty for the first skipped character between two matched characters
+const PENALTY_GAP_START: i64 = 3;
+/// Penalty for every further skipped character
+const PENALTY_GAP_EXTENSION: i64 = 1;
+/// Bonus for a match at the start of a word, like `s` in `git_status`
+const BONUS_BOUNDARY: i64 = 8;
+/// Bonus for a match at a camelCase or letter to digit transition
+const BONUS_CAMEL: i64 = 7;
+/// Minimum bonus for a match right after the previous matched character
+const BONUS_CONSECUTIVE: i64 = 4;
+/// The bonus of the first matched character counts this many times
+const FIRST_CHARACTER_MULTIPLIER: i64 = 2;
+
+/// Result of matching a pattern against a text
+///
+/// - `score`: Higher is better. Matches at word starts and consecutive matches score higher, gaps lower
+/// - `positions`: Indices of the matched characters (not bytes) in the text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    pub positions: Vec<usize>,
+}
+
+/// Bit of `c` in a character signature. Letters and digits get a bit each, all other characters share the rest
+fn character_bit(c: char) -> u64 {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    match c {
+        'a'..='z' => 1 << (u32::from(c) - u32::from('a')),
+        '0'..='9' => 1 << (26 + u32::from(c) - u32::from('0')),
+        _ => 1 << (36 + u32::from(c) % 28),
+    }
+}
+
+/// The set of characters in `text` as bits, ignoring case. A text can only match a pattern, fuzzy or exact,
+/// if its signature contains all bits of the pattern's signature
+pub fn signature(text: &str) -> u64 {
+    text.chars()
+        .fold(0, |signature, c| signature | character_bit(c))
+}
+
+/// Bonus for matching `current` when it follows `previous`
+fn boundary_bonus(previous: Option<char>, current: char) -> i64 {
+    match previous {
+        None => BONUS_BOUNDARY,
+        Some(p) if !p.is_alphanumeric() && current.is_alphanumeric() => BONUS_BOUNDARY,
+        Some(p) if p.is_lowercase() && current.is_uppercase() => BONUS_CAMEL,
+        Some(p) if p.is_alphabetic() && current.is_numeric() => BONUS_CAMEL,
+        _ => 0,
+    }
+}
+
+/// A search pattern prepared for matching many texts
+///
+/// Matching ignores case, unless the pattern contains an uppercase character
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    chars: Vec<char>,
+    signature: u64,
+    is_case_sensitive: bool,
+    is_exact: bool,
+}
+
+impl Pattern {
+    /// Pattern whose characters have to appear in order, with anything in between.
+    /// Among all occurrences the shortest one ending at the first possible position is scored
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// let matched = Pattern::fuzzy("gst").find("git_status").unwrap();
+    /// assert_eq!(vec![0, 4, 5], matched.positions);
+    /// ```
+    pub fn fuzzy(pattern: &str) -> Self {
+        let is_case_sensitive = pattern.chars().any(char::is_uppercase);
+        let mut prepared = Self {
+            chars: Vec::new(),
+            signature: signature(pattern),
+            is_case_sensitive,
+            is_exact: false,
+        };
+        prepared.chars = pattern.chars().map(|c| prepared.normalize(c)).collect();
+        prepared
+    }
+
+    /// Pattern which has to appear as one piece, like a phrase in quotes.
+    /// The first occurrence is scored as if all its characters matched consecutively
+    pub fn exact(pattern: &str) -> Self {
+        Self {
+            is_exact: true,
+            ..Self::fuzzy(pattern)
+        }
+    }
+
+    fn normalize(&self, c: char) -> char {
+        if self.is_case_sensitive {
+            c
+        } else if c.is_ascii() {
+            c.to_ascii_lowercase()
+        } else {
+            c.to_lowercase().next().unwrap_or(c)
+        }
+    }
+
+    /// Signature of the characters of the pattern, see [`signature`]
+    pub const fn signature(&self) -> u64 {
+        self.signature
+    }
+
+    /// Highest score any text can reach, when all characters match consecutively at a word start
+    pub fn max_score(&self) -> i64 {
+        let length = i64::try_from(self.chars.len()).unwrap_or(i64::MAX);
+        length * (SCORE_MATCH + BONUS_BOUNDARY) + BONUS_BOUNDARY * (FIRST_CHARACTER_MULTIPLIER - 1)
+    }
+
+    /// `false` if a text with the [`signature`] `text_signature` cannot match, so it does not need to be searched
+    pub const fn could_match(&self, text_signature: u64) -> bool {
+        text_signature & self.signature == self.signature
+    }
+
+    /// Score of the best occurrence in `text`, without collecting its positions
+    pub fn score(&self, text: &str) -> Option<i64> {
+        self.find_in(text, None)
+    }
+
+    /// Score and positions of the best occurrence in `text`
+    pub fn find(&self, text: &str) -> Option<FuzzyMatch> {
+        let mut positions = Vec::with_capacity(self.chars.len());
+        let score = self.find_in(text, Some(&mut positions))?;
+        Some(FuzzyMatch { score, positions })
+    }
+
+    fn find_in(&self, text: &str, positions: Option<&mut Vec<usize>>) -> Option<i64> {
+        if self.chars.is_empty() {
+            return Some(0);
+        }
+        // ASCII texts are by far the most common, they can be matched by byte without collecting their characters
+        if text.is_ascii() {
+            let bytes = text.as_bytes();
+            let at = |i: usize| char::from(bytes[i]);
+            if self.is_case_sensitive {
+                self.find_by(bytes.len(), at, at, positions)
+            } else {
+                let lowercase_at = |i: usize| char::from(bytes[i].to_ascii_lowercase());
+                self.find_by(bytes.len(), at, lowercase_at, positions)
+            }
+        } else {
+            let text: Vec<char> = text.chars().collect();
+            let normalized: Vec<char> = text.iter().map(|c| self.normalize(*c)).collect();
+            self.find_by(text.len(), |i| text[i], |i| normalized[i], positions)
+        }
+    }
+
+    /// Find and score an occurrence in a text of `length` characters.
+    /// `at` returns the character at an index, `normalized_at` the character to compare with the pattern
+    fn find_by<A, N>(
+        &self,
+        length: usize,
+        at: A,
+        normalized_at: N,
+        positions: Option<&mut Vec<usize>>,
+    ) -> Option<i64>
+    where
+        A: Fn(usize) -> char,
+        N: Fn(usize) -> char,
+    {
+        if self.is_exact {
+            self.find_exact(length, at, normalized_at, positions)
+        } else {
+            self.find_fuzzy(length, at, normalized_at, positions)
+        }
+    }
+
+    /// Among all occurrences the shortest one ending at the first possible position is scored
+    fn find_fuzzy<A, N>(
+        &self,
+        length: usize,
+        at: A,
+        normalized_at: N,
+        mut positions: Option<&mut Vec<usize>>,
+    ) -> Option<i64>
+    where
+        A: Fn(usize) -> char,
+        N: Fn(usize) -> char,
+    {
+        let pattern = &self.chars;
+
+        // Find where the first occurrence of the whole pattern ends
+        let mut pattern_index = 0;
+        let mut end = None;
+        for index in 0..length {
+            if normalized_at(index) == pattern[pattern_index] {
+                pattern_index += 1;
+                if pattern_index == pattern.len() {
+                    end = Some(index);
+                    break;
+                }
+            }
+        }
+        let end = end?;
+
+        // Walk back from there to find the shortest occurrence
+        let mut start = end;
+        let mut pattern_index = pattern.len();
+        for index in (0..=end).rev() {
+            if normalized_at(index) == pattern[pattern_index - 1] {
+                pattern_index -= 1;
+                if pattern_index == 0 {
+                    start = index;
+                    break;
+                }
+            }
+        }
+
+        let mut score = 0;
+        let mut last_position = None;
+        let mut pattern_index = 0;
+        let mut chunk_bonus = 0;
+        let mut is_in_gap = false;
+        for index in start..=end {
+            let c = at(index);
+            if pattern_index < pattern.len() && normalized_at(index) == pattern[pattern_index] {
+                let previous_index = index.checked_sub(1);
+                let mut bonus = boundary_bonus(previous_index.map(&at), c);
+                if previous_index.is_some() && previous_index == last_position {
+                    // Consecutive matches keep the bonus of the word start they belong to
+                    bonus = bonus.max(chunk_bonus).max(BONUS_CONSECUTIVE);
+                } else {
+                    chunk_bonus = bonus;
+                }
+                if pattern_index == 0 {
+                    bonus *= FIRST_CHARACTER_MULTIPLIER;
+                }
+                score += SCORE_MATCH + bonus;
+                if let Some(positions) = positions.as_deref_mut() {
+                    positions.push(index);
+                }
+                last_position = Some(index);
+                pattern_index += 1;
+                is_in_gap = false;
+            } else {
+                score -= if is_in_gap {
+                    PENALTY_GAP_EXTENSION
+                } else {
+                    PENALTY_GAP_START
+                };
+                is_in_gap = true;
+            }
+        }
+        Some(score)
+    }
+
+    /// The first occurrence is scored as if all its characters matched consecutively
+    fn find_exact<A, N>(
+        &self,
+        length: usize,
+        at: A,
+        normalized_at: N,
+        positions: Option<&mut Vec<usize>>,
+    ) -> Option<i64>
+    where
+        A: Fn(usize) -> char,
+        N: Fn(usize) -> char,
+    {
+        let pattern = &self.chars;
+        let start = (0..(length + 1).checked_sub(pattern.len())?).find(|start| {
+            pattern
+                .iter()
+                .enumerate()
+                .all(|(i, c)| normalized_at(start + i) == *c)
+        })?;
+        let previous = start.checked_sub(1).map(&at);
+        let first_bonus = boundary_bonus(previous, at(start)).max(BONUS_CONSECUTIVE);
+        let pattern_length = i64::try_from(pattern.len()).unwrap_or(i64::MAX);
+        if let Some(positions) = positions {
+            positions.extend(start..start + pattern.len());
+        }
+        Some(
+            pattern_length * (SCORE_MATCH + first_bonus)
+                + first_bonus * (FIRST_CHARACTER_MULTIPLIER - 1),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_fuzzy {
+    use super::*;
+
+    fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+        Pattern::fuzzy(pattern).find(text)
+    }
+
+    fn exact_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+        Pattern::exact(pattern).find(text)
+    }
+
+    fn score(pattern: &str, text: &str) -> i64 {
+        fuzzy_match(pattern, text).unwrap().score
+    }
+
+    #[test]
+    fn match_positions() {
+        assert_eq!(
+            vec![0, 4, 5],
+            fuzzy_match("gst", "git_status").unwrap().positions
+        );
+        assert_eq!(
+            vec![7, 8, 9],
+            fuzzy_match("log", "git_lo_log").unwrap().positions
+        );
+        assert!(fuzzy_match("gts", "git_status").is_some());
+        assert!(fuzzy_match("gsx", "git_status").is_none());
+    }
+
+    #[test]
+    fn signatures() {
+        assert_eq!(signature("abc"), signature("CBAcab"));
+        assert!(Pattern::fuzzy("gst").could_match(signature("git_status")));
+        assert!(!Pattern::fuzzy("gsx").could_match(signature("git_status")));
+    }
+
+    #[test]
+    fn empty_pattern_matches_everything() {
+        assert_eq!(0, score("", "anything"));
+    }
+
+    #[test]
+    fn smart_case() {
+        assert!(fuzzy_match("GST", "git_status").is_none());
+        assert!(fuzzy_match("gst", "GIT_STATUS").is_some());
+        assert!(fuzzy_match("gS", "git_Status").is_some());
+    }
+
+    #[test]
+    fn word_starts_and_consecutive_matches_score_higher() {
+        assert!(score("gs", "git_status") > score("gs", "bugs"));
+        assert!(score("gst", "git_status") > score("gst", "eggs_t"));
+        assert!(score("stat", "git_status") > score("stat", "s_t_a_t"));
+        assert!(score("push", "push") > score("push", "pxuxsxh"));
+    }
+
+    #[test]
+    fn max_score_is_an_upper_bound() {
+        for (pattern, text) in [("gst", "git_status"), ("push", "push"), ("ab", "xAyB")] {
+            assert!(score(pattern, text) <= Pattern::fuzzy(pattern).max_score());
+        }
+        assert_eq!(
+            exact_match("push", "push").unwrap().score,
+            Pattern::exact("push").max_score()
+        );
+    }
+
+    #[test]
+    fn camel_case_boundaries() {
+        assert!(score("gs", "gitStatus") > score("gs", "gitstatus"));
+    }
+
+    #[test]
+    fn unicode_positions_are_characters() {
+        assert_eq!(vec![2], fuzzy_match("ß", "äöß").unwrap().positions);
+    }
+
+    #[test]
+    fn exact_phrases() {
+        assert_eq!(
+            vec![4, 5, 6, 7, 8, 9, 10, 11],
+            exact_match("git push", "run Git Push").unwrap().positions
+        );
+        assert!(exact_match("Git Push", "run git push").is_none());
+        assert!(exact_match("git push", "git status && push").is_none());
+        assert!(score("push", "push") <= exact_match("push", "push").unwrap().score);
+    }
+}
@@ -1,4 +1,6 @@
 use crate::config::HoardConfig;
+use crate::core::context::UsageContext;
+use crate::core::ranking::{Ranking, Sort};
 use crate::core::trove::Trove;
 use crate::core::HoardCmd;
 use crate::gpt::prompt;
@@ -13,10 +15,13 @@ use crate::gui::new_command::controls::key_handler as key_handler_create_command
 use crate::gui::new_command::render::draw as draw_new_command_input;
 use crate::gui::parameter_input::controls::key_handler as key_handler_parameter_input;
 use crate::gui::parameter_input::render::draw as draw_parameter_input;
+use crate::query::Query;
+use crate::search_index::SearchIndex;
 use eyre::Result;
 use ratatui::{backend::TermionBackend, widgets::ListState, Terminal};
 use std::fmt;
 use std::io::stdout;
+use std::rc::Rc;
 use std::time::Duration;
 use termion::raw::IntoRawMode;
 use termion::screen::IntoAlternateScreen;
@@ -25,12 +30,16 @@ use termion::screen::IntoAlternateScreen;
 pub struct State {
     pub buffered_tick: bool,
     pub command_list: ListState,
-    pub commands: Vec<HoardCmd>,
+    /// Commands in the list, shared with the search index
+    pub commands: Vec<Rc<HoardCmd>>,
+    pub context: Option<UsageContext>,
     pub control: ControlState,
     pub draw: DrawState,
     pub edit_selection: EditSelection,
     pub error_message: String,
+    pub index: SearchIndex,
     pub input: String,
+    pub is_context_only: bool,
     pub namespace_tab: ListState,
     pub new_command: Option<HoardCmd>,
     pub openai_key_set: bool,
@@ -38,17 +47,32 @@ pub struct State {
     pub parameter_token: String,
     pub popup_message: String,
     pub provided_parameter_count: u16,
+    pub query: Query,
+    pub query_error: Option<String>,
     pub query_gpt: bool,
+    pub ranking: Ranking,
     pub selected_command: Option<HoardCmd>,
     pub should_delete: bool,
+    pub should_edit_in_editor: bool,
     pub should_exit: bool,
+    pub sort: Option<Sort>,
+    /// Shown in the footer until the next key is pressed, like after copying a command
+    pub status_message: Option<String>,
     pub string_to_edit: String,
 }
 
 impl State {
+    /// List all commands, ranked or sorted if a sort order is picked
+    pub fn show_all_commands(&mut self) {
+        self.commands = self.index.commands().cloned().collect();
+        if let Some(sort) = self.sort {
+            sort.apply(&mut self.commands);
+        }
+    }
+
     pub fn update_string_to_edit(&mut self) -> &mut Self {
         let selected_idx = self.command_list.selected().unwrap();
-        let cloned_selected_command = self.commands.get(selected_idx).unwrap().clone();
+        let cloned_selected_command = HoardCmd::clone(self.commands.get(selected_idx).unwrap());
         match self.edit_selection {
             EditSelection::Name => self.string_to_edit = cloned_selected_command.name,
             EditSelection::Tags => {
@@ -146,8 +170,16 @@ impl EditSelection {
     }
 }
 
+/// Why the list was closed with a command
+pub enum Outcome {
+    /// The command to put into the shell
+    Selected(HoardCmd),
+    /// The command to edit in `$EDITOR`, the list opens again afterwards
+    EditInEditor(HoardCmd),
+}
+
 #[allow(clippy::too_many_lines)]
-pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>> {
+pub fn run(trove: &mut Trove, config: &HoardConfig, sort: Option<Sort>) -> Result<Option<Outcome>> {
     let events = Events::with_config(Config {
         tick_rate: Duration::from_millis(200),
     });
@@ -158,17 +190,25 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
         openai_api_key = config.gpt_api_key.clone().unwrap_or_default();
     }
 
+    let ranking = config.ranking.unwrap_or_default();
+    let context = UsageContext::current();
     let mut app_state = State {
         input: String::new(),
-        commands: trove.get_commands_sorted_by_usage(),
+        commands: Vec::new(),
+        index: SearchIndex::new(&trove.commands, ranking, context.clone()),
+        context,
+        is_context_only: false,
         command_list: ListState::default(),
         namespace_tab: ListState::default(),
         should_exit: false,
+        sort,
         should_delete: false,
+        should_edit_in_editor: false,
         draw: DrawState::Search,
         control: ControlState::Search,
         edit_selection: EditSelection::Command,
         new_command: None,
+        status_message: None,
         string_to_edit: String::new(),
         parameter_token: config.parameter_token.as_ref().unwrap().clone(),
         parameter_ending_token: config.parameter_ending_token.as_ref().unwrap().clone(),
@@ -176,12 +216,16 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
         selected_command: None,
         provided_parameter_count: 0,
         error_message: String::new(),
+        query: Query::default(),
+        query_error: None,
         query_gpt: false,
+        ranking,
         popup_message: State::get_default_popupmsg(),
         buffered_tick: false,
         openai_key_set: !openai_api_key.is_empty(),
     };
 
+    app_state.show_all_commands();
     app_state.command_list.select(Some(0));
     app_state.namespace_tab.select(Some(0));
 
@@ -219,8 +263,11 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
         if app_state.query_gpt && app_state.control == ControlState::Gpt {
             if app_state.buffered_tick {
                 let gpt_command = prompt(&app_state.input[..], &openai_api_key);
-                let _ = trove.add_command(gpt_command, false);
-                app_state.commands = trove.commands.clone();
+                if matches!(trove.add_command(gpt_command, false), Ok(true)) {
+                    let added = trove.commands.last().expect("command was just added");
+                    app_state.index.insert(added.clone());
+                }
+                app_state.show_all_commands();
                 app_state.draw = DrawState::Search;
                 app_state.control = ControlState::Search;
                 app_state.input = String::new();
@@ -234,12 +281,9 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
         if let Event::Input(input) = events.next()? {
             let command = match app_state.draw {
                 DrawState::Search => match app_state.control {
-                    ControlState::Search => key_handler_list_search(
-                        input,
-                        &mut app_state,
-                        &trove.commands,
-                        &namespace_tabs,
-                    ),
+                    ControlState::Search => {
+                        key_handler_list_search(input, &mut app_state, &namespace_tabs)
+                    }
                     ControlState::Edit => key_handler_inline_edit(input, &mut app_state),
                     ControlState::Gpt => key_handler_gpt_create(input, &mut app_state),
                     ControlState::KeyNotSet => key_handler_no_key_set(input, &mut app_state),
@@ -253,27 +297,40 @@ pub fn run(trove: &mut Trove, config: &HoardConfig) -> Result<Option<HoardCmd>>
 
             if let Some(output) = command {
                 if app_state.draw == DrawState::Create {
-                    let _ = trove.add_command(output, true);
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+                    if matches!(trove.add_command(output, true), Ok(true)) {
+                        let added = trove.commands.last().expect("command was just added");
+                        app_state.index.insert(added.clone());
+                    }
+                    app_state.show_all_commands();
                     app_state.draw = DrawState::Search;
                 } else if app_state.control == ControlState::Edit {
                     // Command has been edited
                     trove.update_command_by_name(&output);
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+                    let edited = trove
+                        .commands
+                        .iter()
+                        .find(|c| c.name == output.name && !c.is_read_only);
+                    if let Some(edited) = edited {
+                        app_state.index.update_by_name(edited);
+                    }
+                    app_state.show_all_commands();
                     app_state.control = ControlState::Search;
                 } else if app_state.should_delete {
-                    trove.remove_command(&output.name).ok();
-                    app_state.commands = trove.commands.clone();
-                    app_state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+                    if trove.remove_command(&output.name).is_ok() {
+                        app_state.index.remove(&output.name);
+                    }
+                    app_state.show_all_commands();
                     app_state.should_delete = false;
+                } else if app_state.should_edit_in_editor {
+                    // The editor needs the terminal, so the list is closed while it is open
+                    terminal.show_cursor()?;
+                    return Ok(Some(Outcome::EditInEditor(output)));
                 } else {
                     // Command has been selected
                     // Update the command's meta info
                     let _ = trove.update_command_meta(&output);
                     terminal.show_cursor()?;
-                    return Ok(Some(output));
+                    return Ok(Some(Outcome::Selected(output)));
                 }
             }
 
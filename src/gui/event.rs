@@ -1,10 +1,18 @@
 use crossbeam_channel::unbounded;
-use std::thread;
+use std::fs::File;
+use std::io::Read;
+use std::os::unix::io::AsRawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
 use std::time::Duration;
 
 use termion::event::Key;
 use termion::input::TermRead;
 
+/// How long the input thread waits for a key before it checks whether to stop
+const INPUT_POLL_TIMEOUT: Duration = Duration::from_millis(50);
+
 pub enum Event<I> {
     Input(I),
     Tick,
@@ -15,6 +23,9 @@ pub enum Event<I> {
 #[allow(dead_code)]
 pub struct Events {
     rx: crossbeam_channel::Receiver<Event<Key>>,
+    /// Stops the input thread once the events are dropped, so programs started afterwards get all keys
+    stop_input: Arc<AtomicBool>,
+    input: Option<JoinHandle<()>>,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -39,15 +50,27 @@ impl Events {
     #[allow(clippy::manual_flatten)]
     pub fn with_config(config: Config) -> Self {
         let (tx, rx) = unbounded();
+        let stop_input = Arc::new(AtomicBool::new(false));
 
-        {
+        let input = {
             let tx = tx.clone();
+            let stop_input = Arc::clone(&stop_input);
             thread::spawn(move || {
-                let tty = termion::get_tty().expect("Could not find tty session");
-                for key in tty.keys().flatten() {
-                    if let Err(err) = tx.send(Event::Input(key)) {
-                        eprintln!("{err}");
-                        return;
+                let mut tty = termion::get_tty().expect("Could not find tty session");
+                let mut buffer = [0; 64];
+                while !stop_input.load(Ordering::Relaxed) {
+                    if !is_readable(&tty, INPUT_POLL_TIMEOUT) {
+                        continue;
+                    }
+                    let count = match tty.read(&mut buffer) {
+                        Ok(0) | Err(_) => return,
+                        Ok(count) => count,
+                    };
+                    for key in buffer[..count].keys().flatten() {
+                        if let Err(err) = tx.send(Event::Input(key)) {
+                            eprintln!("{err}");
+                            return;
+                        }
                     }
                 }
             })
@@ -60,10 +83,35 @@ impl Events {
             }
             thread::sleep(config.tick_rate);
         });
-        Self { rx }
+        Self {
+            rx,
+            stop_input,
+            input: Some(input),
+        }
     }
 
     pub fn next(&self) -> Result<Event<Key>, crossbeam_channel::RecvError> {
         self.rx.recv()
     }
 }
+
+impl Drop for Events {
+    fn drop(&mut self) {
+        self.stop_input.store(true, Ordering::Relaxed);
+        if let Some(input) = self.input.take() {
+            let _ = input.join();
+        }
+    }
+}
+
+/// Wait up to `timeout` for `tty` to have input to read
+fn is_readable(tty: &File, timeout: Duration) -> bool {
+    let mut poll_fd = libc::pollfd {
+        fd: tty.as_raw_fd(),
+        events: libc::POLLIN,
+        revents: 0,
+    };
+    let timeout = i32::try_from(timeout.as_millis()).unwrap_or(i32::MAX);
+    // SAFETY: `poll_fd` is a single valid `pollfd` which outlives the call
+    unsafe { libc::poll(&raw mut poll_fd, 1, timeout) > 0 }
+}
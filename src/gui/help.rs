@@ -19,9 +19,14 @@ const HELP_CONTENT: &[(&str, &str)] = &[
     ("Next namespace tab", "<Ctrl-L> / <Right-Arrow>"),
     ("Previous namespace tab", "<Ctrl-H> / <Left-Arrow>"),
     ("Select command", "<Enter>"),
+    ("Only show commands used in this directory", "<Ctrl-T>"),
+    ("Cycle sort field", "<Ctrl-S>"),
+    ("Reverse sort order", "<Ctrl-O>"),
     ("Create new command", "<Ctrl-W>"),
     ("Delete command", "<Ctrl-X>"),
     ("Toggle search/edit mode", "<Tab> / <Ctrl-E>"),
+    ("Edit command as YAML in $EDITOR", "<Ctrl-V>"),
+    ("Copy command to clipboard", "<Ctrl-K>"),
     ("Toggle Command to edit in edit mode", "<Tab>"),
     ("Exit edit mode", "<Esc>"),
     ("Quit", "<Ctrl-D> / <Ctrl-C> / <Ctrl-G>"),
@@ -1,15 +1,14 @@
+use crate::clipboard;
 use crate::core::parameters::Parameterized;
+use crate::core::ranking::Sort;
 use crate::core::HoardCmd;
 use crate::gui::commands_gui::{ControlState, DrawState, EditSelection, State};
+use crate::query::Query;
 use termion::event::Key;
 
 #[allow(clippy::too_many_lines)]
-pub fn key_handler(
-    input: Key,
-    state: &mut State,
-    trove_commands: &[HoardCmd],
-    namespace_tabs: &[&str],
-) -> Option<HoardCmd> {
+pub fn key_handler(input: Key, state: &mut State, namespace_tabs: &[&str]) -> Option<HoardCmd> {
+    state.status_message = None;
     match input {
         Key::Esc | Key::Ctrl('c' | 'd' | 'g') => {
             // Definitely exit program
@@ -42,19 +41,44 @@ pub fn key_handler(
             state.new_command = Some(HoardCmd::default());
             None
         }
+        // Cycle through the fields to sort by
+        Key::Ctrl('s') => {
+            state.sort = Sort::next(state.sort);
+            apply_filter(state, namespace_tabs);
+            state.command_list.select(Some(0));
+            None
+        }
+        // Reverse the sort order
+        Key::Ctrl('o') => {
+            state.sort = state.sort.map(Sort::reversed);
+            apply_filter(state, namespace_tabs);
+            state.command_list.select(Some(0));
+            None
+        }
+        // Only show commands used in this directory
+        Key::Ctrl('t') => {
+            state.is_context_only = !state.is_context_only;
+            apply_filter(state, namespace_tabs);
+            state.command_list.select(Some(0));
+            None
+        }
         // Switch to edit command mode
         Key::Ctrl('e') | Key::Char('\t') => {
-            let selected_command = state
-                .commands
-                .clone()
-                .get(
-                    state
-                        .command_list
-                        .selected()
-                        .expect("there is always a selected command"),
-                )
-                .expect("exists")
-                .clone();
+            let selected_command = HoardCmd::clone(
+                state
+                    .commands
+                    .get(
+                        state
+                            .command_list
+                            .selected()
+                            .expect("there is always a selected command"),
+                    )
+                    .expect("exists"),
+            );
+            if selected_command.is_read_only {
+                // Commands of subscribed troves can not be edited
+                return None;
+            }
             state.control = ControlState::Edit;
             state.selected_command = Some(selected_command);
             state.update_string_to_edit();
@@ -64,14 +88,14 @@ pub fn key_handler(
         Key::Left | Key::Ctrl('h') => {
             if let Some(selected) = state.namespace_tab.selected() {
                 let new_selected_tab = previous_index(selected, namespace_tabs.len());
-                switch_namespace(state, new_selected_tab, namespace_tabs, trove_commands);
+                switch_namespace(state, new_selected_tab, namespace_tabs);
             }
             None
         }
         Key::Right | Key::Ctrl('l') => {
             if let Some(selected) = state.namespace_tab.selected() {
                 let new_selected_tab = next_index(selected, namespace_tabs.len());
-                switch_namespace(state, new_selected_tab, namespace_tabs, trove_commands);
+                switch_namespace(state, new_selected_tab, namespace_tabs);
             }
             None
         }
@@ -98,18 +122,40 @@ pub fn key_handler(
             if state.commands.is_empty() {
                 return None;
             }
+            let selected_command = HoardCmd::clone(
+                state
+                    .commands
+                    .get(
+                        state
+                            .command_list
+                            .selected()
+                            .expect("there is always a selected command"),
+                    )
+                    .expect("exists"),
+            );
+            if selected_command.is_read_only {
+                return None;
+            }
+            state.should_delete = true;
+            Some(selected_command)
+        }
+        // Copy the selected command to the clipboard and keep the list open
+        Key::Ctrl('k') => {
+            let selected_command = state.commands.get(state.command_list.selected()?)?;
+            state.status_message = Some(match clipboard::copy(&selected_command.command) {
+                Ok(()) => format!("Copied [{}] to the clipboard", selected_command.name),
+                Err(e) => e.to_string(),
+            });
+            None
+        }
+        // Edit the selected command as YAML in `$EDITOR`
+        Key::Ctrl('v') => {
             let selected_command = state
                 .commands
-                .clone()
-                .get(
-                    state
-                        .command_list
-                        .selected()
-                        .expect("there is always a selected command"),
-                )
-                .expect("exists")
-                .clone();
-            state.should_delete = true;
+                .get(state.command_list.selected()?)
+                .filter(|c| !c.is_read_only)
+                .map(|c| HoardCmd::clone(c))?;
+            state.should_edit_in_editor = true;
             Some(selected_command)
         }
         // Select command
@@ -118,17 +164,17 @@ pub fn key_handler(
                 state.should_exit = true;
                 return None;
             }
-            let selected_command = state
-                .commands
-                .clone()
-                .get(
-                    state
-                        .command_list
-                        .selected()
-                        .expect("there is always a selected command"),
-                )
-                .expect("exists")
-                .clone();
+            let selected_command = HoardCmd::clone(
+                state
+                    .commands
+                    .get(
+                        state
+                            .command_list
+                            .selected()
+                            .expect("there is always a selected command"),
+                    )
+                    .expect("exists"),
+            );
             // Check if parameters need to be supplied
             if selected_command.get_parameter_count(&state.parameter_token) > 0 {
                 // Set next state to draw
@@ -145,12 +191,12 @@ pub fn key_handler(
         // Handle query input
         Key::Backspace => {
             state.input.pop();
-            apply_filter(state, namespace_tabs, trove_commands);
+            apply_filter(state, namespace_tabs);
             None
         }
         Key::Char(c) => {
             state.input.push(c);
-            apply_filter(state, namespace_tabs, trove_commands);
+            apply_filter(state, namespace_tabs);
             None
         }
         _ => None,
@@ -173,19 +219,14 @@ const fn previous_index(current_index: usize, collection_length: usize) -> usize
     }
 }
 
-fn switch_namespace(
-    state: &mut State,
-    index_to_select: usize,
-    namespaces: &[&str],
-    commands: &[HoardCmd],
-) {
+fn switch_namespace(state: &mut State, index_to_select: usize, namespaces: &[&str]) {
     state.namespace_tab.select(Some(index_to_select));
 
     let selected_namespace = namespaces
         .get(index_to_select)
         .expect("Always a tab selected");
 
-    apply_search(state, commands, selected_namespace);
+    apply_search(state, selected_namespace);
 
     let new_selected_command = if state.commands.is_empty() {
         0
@@ -196,24 +237,27 @@ fn switch_namespace(
     state.command_list.select(Some(new_selected_command));
 }
 
-fn apply_search(state: &mut State, all_commands: &[HoardCmd], selected_tab: &str) {
-    let query_term = &state.input[..];
-    state.commands = all_commands
-        .iter()
-        .filter(|&c| {
-            (c.name.contains(query_term)
-                || c.namespace.contains(query_term)
-                || c.get_tags_as_string().contains(query_term)
-                || c.command.contains(query_term)
-                || c.description.contains(query_term))
-                && (c.namespace.clone() == *selected_tab || selected_tab == "All")
-        })
-        .cloned()
-        .collect();
-    state.commands.sort_by(|a, b| b.usage_count.cmp(&a.usage_count));
+fn apply_search(state: &mut State, selected_tab: &str) {
+    // Keep the last valid query while the input has syntax errors, like an unfinished `used:>`
+    match Query::parse(&state.input) {
+        Ok(query) => {
+            state.query = query;
+            state.query_error = None;
+        }
+        Err(e) => state.query_error = Some(e.to_string()),
+    }
+    // Best matches first, and among equally good matches the best ranked commands
+    let matches = state.index.search(&state.query, |c| {
+        (selected_tab == "All" || c.namespace == selected_tab)
+            && (!state.is_context_only || state.context.as_ref().is_some_and(|x| x.is_used_here(c)))
+    });
+    state.commands = matches.into_iter().cloned().collect();
+    if let Some(sort) = state.sort {
+        sort.apply(&mut state.commands);
+    }
 }
 
-fn apply_filter(state: &mut State, namespaces: &[&str], commands: &[HoardCmd]) {
+fn apply_filter(state: &mut State, namespaces: &[&str]) {
     let selected_tab = namespaces
         .get(
             state
@@ -222,13 +266,18 @@ fn apply_filter(state: &mut State, namespaces: &[&str], commands: &[HoardCmd]) {
                 .expect("Always a namespace selected"),
         )
         .expect("Always a tab selected");
-    apply_search(state, commands, selected_tab);
+    apply_search(state, selected_tab);
 }
 
 #[cfg(test)]
 mod test_controls {
     use super::*;
+    use crate::core::context::UsageContext;
+    use crate::core::ranking::Ranking;
+    use crate::search_index::SearchIndex;
     use ratatui::widgets::ListState;
+    use std::rc::Rc;
+    use std::time::{Duration, Instant};
 
     const DEFAULT_NAMESPACE: &str = "default";
 
@@ -242,14 +291,20 @@ mod test_controls {
     fn create_state(commands: Vec<HoardCmd>) -> State {
         let mut state = State {
             input: String::new(),
-            commands,
+            index: SearchIndex::new(&commands, Ranking::Usage, None),
+            commands: commands.into_iter().map(Rc::new).collect(),
+            context: None,
+            is_context_only: false,
             command_list: ListState::default(),
             namespace_tab: ListState::default(),
             should_exit: false,
+            sort: None,
             should_delete: false,
+            should_edit_in_editor: false,
             draw: DrawState::Search,
             control: ControlState::Search,
             new_command: None,
+            status_message: None,
             edit_selection: crate::gui::commands_gui::EditSelection::Command,
             string_to_edit: String::new(),
             parameter_token: "#".to_string(),
@@ -257,7 +312,10 @@ mod test_controls {
             selected_command: None,
             provided_parameter_count: 0,
             error_message: String::new(),
+            query: Query::default(),
+            query_error: None,
             query_gpt: false,
+            ranking: Ranking::Usage,
             buffered_tick: false,
             popup_message: State::get_default_popupmsg(),
             openai_key_set: false,
@@ -277,8 +335,7 @@ mod test_controls {
         let mut state = create_state(vec![cmd1, cmd2, cmd3]);
         state.command_list.select(Some(initial_index));
 
-        let commands = state.commands.clone();
-        key_handler(key, &mut state, &commands, &namespaces);
+        key_handler(key, &mut state, &namespaces);
         let new_selected_index = state.command_list.selected();
 
         assert_eq!(expected_index, new_selected_index.unwrap());
@@ -289,8 +346,7 @@ mod test_controls {
         let mut state = create_state(vec![]);
         state.namespace_tab.select(Some(initial_index));
 
-        let commands = state.commands.clone();
-        key_handler(key, &mut state, &commands, &namespaces);
+        key_handler(key, &mut state, &namespaces);
         let new_selected_index = state.namespace_tab.selected();
 
         assert_eq!(expected_index, new_selected_index.unwrap());
@@ -349,14 +405,34 @@ mod test_controls {
         let cmd2 = create_command(cmd2_name, "", namespace2);
         let mut state = create_state(vec![cmd1, cmd2]);
 
-        let commands = state.commands.clone();
-        key_handler(Key::Right, &mut state, &commands, &all_namespaces);
+        key_handler(Key::Right, &mut state, &all_namespaces);
         let filtered_commands = state.commands;
 
         assert_eq!(1, filtered_commands.len());
         assert_eq!(cmd2_name, filtered_commands.first().unwrap().name);
     }
 
+    #[test]
+    fn toggle_commands_used_in_this_directory() {
+        let all_namespaces = vec![DEFAULT_NAMESPACE];
+        let context = UsageContext::of(std::path::PathBuf::from("/work/project"));
+        let mut used_here = create_command("used_here", "", DEFAULT_NAMESPACE);
+        used_here.mut_record_context(&context);
+        let elsewhere = create_command("elsewhere", "", DEFAULT_NAMESPACE);
+        let commands = vec![elsewhere, used_here];
+        let mut state = create_state(commands.clone());
+        state.index = SearchIndex::new(&commands, Ranking::Usage, Some(context.clone()));
+        state.context = Some(context);
+
+        key_handler(Key::Ctrl('t'), &mut state, &all_namespaces);
+        assert_eq!(1, state.commands.len());
+        assert_eq!("used_here", state.commands[0].name);
+
+        key_handler(Key::Ctrl('t'), &mut state, &all_namespaces);
+        assert_eq!(2, state.commands.len());
+        assert_eq!("used_here", state.commands[0].name);
+    }
+
     #[test]
     fn select_last_command_when_namespace_changed() {
         let namespace1 = "first_namespace";
@@ -368,8 +444,7 @@ mod test_controls {
         let cmd2 = create_command("second_command", "", namespace2);
         let mut state = create_state(vec![cmd1, cmd2]);
 
-        let commands = state.commands.clone();
-        key_handler(Key::Right, &mut state, &commands, &all_namespaces);
+        key_handler(Key::Right, &mut state, &all_namespaces);
         let selected_command_index = state.command_list.selected().unwrap();
 
         assert_eq!(expected_command_index, selected_command_index);
@@ -386,9 +461,7 @@ mod test_controls {
         let mut state = create_state(vec![cmd1, cmd2]);
         state.command_list.select(Some(command_index));
 
-        let commands = state.commands.clone();
-        let actual_command =
-            key_handler(Key::Char('\n'), &mut state, &commands, &namespaces).unwrap();
+        let actual_command = key_handler(Key::Char('\n'), &mut state, &namespaces).unwrap();
 
         assert_eq!(expected_command, actual_command.name);
     }
@@ -399,8 +472,7 @@ mod test_controls {
         let cmd = create_command("First", "first_command #", DEFAULT_NAMESPACE);
 
         let mut state = create_state(vec![cmd]);
-        let commands = state.commands.clone();
-        key_handler(Key::Char('\n'), &mut state, &commands, &namespaces);
+        key_handler(Key::Char('\n'), &mut state, &namespaces);
 
         assert_eq!(DrawState::ParameterInput, state.draw);
     }
@@ -409,7 +481,7 @@ mod test_controls {
     fn quit_on_nothing_to_pick() {
         let mut state = create_state(vec![]);
 
-        key_handler(Key::Char('\n'), &mut state, &[], &[]);
+        key_handler(Key::Char('\n'), &mut state, &[]);
 
         assert!(state.should_exit);
     }
@@ -418,7 +490,7 @@ mod test_controls {
     fn quit() {
         let mut state = create_state(vec![]);
 
-        key_handler(Key::Esc, &mut state, &[], &[]);
+        key_handler(Key::Esc, &mut state, &[]);
 
         assert!(state.should_exit);
     }
@@ -427,8 +499,137 @@ mod test_controls {
     fn show_help() {
         let mut state = create_state(vec![]);
 
-        key_handler(Key::F(1), &mut state, &[], &[]);
+        key_handler(Key::F(1), &mut state, &[]);
 
         assert_eq!(DrawState::Help, state.draw);
     }
+
+    /// Commands with names, namespaces, tags and descriptions made of common command line words
+    fn generated_commands(count: usize) -> Vec<HoardCmd> {
+        const WORDS: [&str; 24] = [
+            "git",
+            "docker",
+            "kubectl",
+            "status",
+            "push",
+            "pull",
+            "logs",
+            "deploy",
+            "build",
+            "remote",
+            "branch",
+            "container",
+            "image",
+            "namespace",
+            "pod",
+            "service",
+            "restart",
+            "config",
+            "secret",
+            "volume",
+            "network",
+            "commit",
+            "rebase",
+            "cluster",
+        ];
+        let word = |seed: usize| WORDS[seed % WORDS.len()];
+        (0..count)
+            .map(|i| {
+                let seed = i.wrapping_mul(2_654_435_761);
+                HoardCmd::default()
+                    .with_name(&format!("{}_{}_{i}", word(seed), word(seed >> 5)))
+                    .with_namespace(word(seed >> 10))
+                    .with_command(&format!(
+                        "{} {} --{} {} #param!",
+                        word(seed >> 3),
+                        word(seed >> 7),
+                        word(seed >> 11),
+                        word(seed >> 13)
+                    ))
+                    .with_description(&format!(
+                        "{} the {} of a {} in the {}",
+                        word(seed >> 2),
+                        word(seed >> 9),
+                        word(seed >> 12),
+                        word(seed >> 15)
+                    ))
+                    .with_tags_raw(&format!("{},{}", word(seed >> 4), word(seed >> 8)))
+            })
+            .collect()
+    }
+
+    /// Run with `cargo test --release -- --ignored search_50k_commands`.
+    /// Times what typing a key does: searching, and putting the matches into the list
+    #[test]
+    #[ignore = "benchmark, only meaningful in release builds"]
+    fn search_50k_commands() {
+        let started = Instant::now();
+        let mut state = create_state(generated_commands(50_000));
+        println!(
+            "Indexed {} commands in {:?}",
+            state.index.commands().count(),
+            started.elapsed()
+        );
+
+        for query in [
+            "g",
+            "gst",
+            "docker ps",
+            "kubectl restart pod",
+            "ns:git push -tag:remote",
+            "\"restart the\" used:<5",
+            "xyz",
+        ] {
+            state.input = query.to_string();
+            let mut timings: Vec<Duration> = (0..11)
+                .map(|_| {
+                    let started = Instant::now();
+                    apply_search(&mut state, "All");
+                    started.elapsed()
+                })
+                .collect();
+            timings.sort();
+            let median = timings[timings.len() / 2];
+            println!("{query}: {median:?} for {} matches", state.commands.len());
+            assert!(
+                median < Duration::from_millis(10),
+                "Searching {query} took {median:?}"
+            );
+        }
+    }
+
+    /// Run with `cargo test --release -- --ignored update_50k_commands`.
+    /// Times adding, editing and deleting a command in the list, with the list shown again
+    #[test]
+    #[ignore = "benchmark, only meaningful in release builds"]
+    fn update_50k_commands() {
+        let mut state = create_state(generated_commands(50_000));
+        let added = HoardCmd::default()
+            .with_name("added")
+            .with_namespace("git")
+            .with_command("git status");
+        let edited = added.clone().with_command("git status --short");
+        for update in ["add", "edit", "delete"] {
+            let mut timings: Vec<Duration> = (0..11)
+                .map(|_| {
+                    state.index.insert(added.clone());
+                    let started = Instant::now();
+                    match update {
+                        "add" => state.index.insert(added.clone()),
+                        "edit" => state.index.update_by_name(&edited),
+                        _ => state.index.remove("added"),
+                    }
+                    state.show_all_commands();
+                    started.elapsed()
+                })
+                .collect();
+            timings.sort();
+            let median = timings[timings.len() / 2];
+            println!("{update}: {median:?}");
+            assert!(
+                median < Duration::from_millis(10),
+                "Updating with {update} took {median:?}"
+            );
+        }
+    }
 }
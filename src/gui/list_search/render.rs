@@ -1,12 +1,13 @@
 use crate::config::HoardConfig;
 use crate::core::HoardCmd;
+use crate::filter::Field;
 use crate::gui::commands_gui::State;
 use crate::gui::commands_gui::{ControlState, EditSelection};
 use crate::gui::help::HELP_KEY;
 use ratatui::backend::TermionBackend;
 use ratatui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Span};
+use ratatui::text::{Line, Span, Text};
 use ratatui::widgets::{Block, BorderType, Borders, Clear, List, ListItem, Paragraph, Tabs, Wrap};
 use ratatui::Terminal;
 use termion::screen::AlternateScreen;
@@ -96,8 +97,12 @@ pub fn draw(
                 .as_ref(),
             )
             .split(commands_chunks[1]);
+        // If somehow the selection is past the last command, select the last one
+        if app_state.command_list.selected().unwrap_or_default() >= app_state.commands.len() {
+            app_state.command_list.select(Some(app_state.commands.len().saturating_sub(1)));
+        }
         let (commands, command, tags_widget, description, input) =
-            render_commands(&app_state.commands.clone(), app_state, config);
+            render_commands(app_state, config, usize::from(commands_chunks[0].height));
         rect.render_stateful_widget(
             commands,
             commands_chunks[0],
@@ -118,16 +123,24 @@ pub fn draw(
             ])
             .split(chunks[3]);
 
-        let control_str = &app_state.control;
-        let help_hint_l = Paragraph::new(format!("{control_str}"))
+        // Syntax errors of the query replace the hint, as long as the query is edited, then status messages
+        let control_str = match (&app_state.control, &app_state.query_error, &app_state.status_message) {
+            (ControlState::Search, Some(query_error), _) => query_error.clone(),
+            (ControlState::Search, None, Some(status_message)) => status_message.clone(),
+            (control, ..) => control.to_string(),
+        };
+        let help_hint_l = Paragraph::new(control_str)
             .style(Style::default().fg(Color::Rgb(
                 config.primary_color.unwrap().0,
                 config.primary_color.unwrap().1,
                 config.primary_color.unwrap().2,
             )))
             .alignment(Alignment::Left);
+        let sort = app_state
+            .sort
+            .map_or_else(|| app_state.ranking.to_string(), |sort| sort.to_string());
         let help_hint = Paragraph::new(format!(
-            "Create <Ctrl-W> | Delete <Ctrl-X> | GPT <Ctrl-A> | Help {HELP_KEY}"
+            "Sort: {sort} <Ctrl-S> | Create <Ctrl-W> | Delete <Ctrl-X> | GPT <Ctrl-A> | Help {HELP_KEY}"
         ))
         .style(Style::default().fg(Color::Rgb(
             config.primary_color.unwrap().0,
@@ -239,11 +252,45 @@ fn coerce_string_by_mode(s: String, app: &State, command_render: &EditSelection)
     }
 }
 
+/// Split `text` into lines, styling the characters at the sorted `positions` with `highlight`
+fn highlight_matches<'a>(text: &str, positions: &[usize], highlight: Style) -> Text<'a> {
+    let mut lines = Vec::new();
+    let mut spans = Vec::new();
+    let mut chunk = String::new();
+    let mut is_chunk_highlighted = false;
+    let flush = |spans: &mut Vec<Span<'a>>, chunk: &mut String, is_highlighted: bool| {
+        if !chunk.is_empty() {
+            let style = if is_highlighted {
+                highlight
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(std::mem::take(chunk), style));
+        }
+    };
+    for (index, c) in text.chars().enumerate() {
+        if c == '\n' {
+            flush(&mut spans, &mut chunk, is_chunk_highlighted);
+            lines.push(Line::from(std::mem::take(&mut spans)));
+            continue;
+        }
+        let is_highlighted = positions.binary_search(&index).is_ok();
+        if is_highlighted != is_chunk_highlighted {
+            flush(&mut spans, &mut chunk, is_chunk_highlighted);
+            is_chunk_highlighted = is_highlighted;
+        }
+        chunk.push(c);
+    }
+    flush(&mut spans, &mut chunk, is_chunk_highlighted);
+    lines.push(Line::from(spans));
+    Text::from(lines)
+}
+
 #[allow(clippy::too_many_lines)]
 fn render_commands<'a>(
-    commands_list: &[HoardCmd],
-    app: &mut State,
+    app: &State,
     config: &HoardConfig,
+    visible_rows: usize,
 ) -> (
     List<'a>,
     Paragraph<'a>,
@@ -254,37 +301,50 @@ fn render_commands<'a>(
     let commands = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(get_color(app, config, &EditSelection::Name)))
-        .title(" Commands ")
+        .title(if app.is_context_only {
+            " Commands (this directory) "
+        } else {
+            " Commands "
+        })
         .border_type(BorderType::Plain);
 
-    let items: Vec<_> = commands_list
+    // Underlined, so matches stay visible on the highlighted row
+    let match_style = Style::default()
+        .fg(Color::Rgb(
+            config.secondary_color.unwrap().0,
+            config.secondary_color.unwrap().1,
+            config.secondary_color.unwrap().2,
+        ))
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+
+    // Only rows around the selection can be on screen, matching the others would be wasted on long lists
+    let selected = app.command_list.selected().unwrap_or_default();
+    let on_screen = selected.saturating_sub(visible_rows)..selected + visible_rows;
+    let items: Vec<_> = app
+        .commands
         .iter()
-        .map(|command| {
-            ListItem::new(Line::from(vec![Span::styled(
-                command.name.clone(),
-                Style::default(),
-            )]))
+        .enumerate()
+        .map(|(row, command)| {
+            let positions = if on_screen.contains(&row) {
+                app.query
+                    .matches(command)
+                    .map(|m| m.positions(Field::Name))
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            ListItem::new(highlight_matches(&command.name, &positions, match_style))
         })
         .collect();
 
-    let selected_command: HoardCmd = commands_list
+    let selected_command: HoardCmd = app
+        .commands
         .get(
             app.command_list
                 .selected()
                 .expect("there is always a selected command"),
         )
-        .get_or_insert(&HoardCmd::default())
-        .clone();
-
-    if selected_command.name.is_empty() {
-        // If somehow the selection is past the last index, set it to the last element
-        let new_selection = if commands_list.is_empty() {
-            0
-        } else {
-            commands_list.len() - 1
-        };
-        app.command_list.select(Some(new_selection));
-    }
+        .map_or_else(HoardCmd::default, |c| HoardCmd::clone(c));
 
     let list = List::new(items).block(commands).highlight_style(
         Style::default()
@@ -301,11 +361,25 @@ fn render_commands<'a>(
             .add_modifier(Modifier::BOLD),
     );
 
-    let hoarded_command_title = format!(" Hoarded command --- Times selected: {} ", selected_command.usage_count);
-    let command = Paragraph::new(coerce_string_by_mode(
-        selected_command.command.clone(),
-        app,
-        &EditSelection::Command,
+    // Matches are only highlighted while searching, not while editing
+    let selected_match = app
+        .query
+        .matches(&selected_command)
+        .filter(|_| app.control == ControlState::Search)
+        .unwrap_or_default();
+
+    let hoarded_command_title = format!(
+        " Hoarded command --- Times selected: {} ",
+        selected_command.usage_count
+    );
+    let command = Paragraph::new(highlight_matches(
+        &coerce_string_by_mode(
+            selected_command.command.clone(),
+            app,
+            &EditSelection::Command,
+        ),
+        &selected_match.positions(Field::Command),
+        match_style,
     ))
     .style(Style::default().fg(Color::Rgb(
         config.primary_color.unwrap().0,
@@ -322,10 +396,14 @@ fn render_commands<'a>(
             .border_type(BorderType::Plain),
     );
 
-    let tags     = Paragraph::new(coerce_string_by_mode(
-        selected_command.get_tags_as_string(),
-        app,
-        &EditSelection::Tags,
+    let tags = Paragraph::new(highlight_matches(
+        &coerce_string_by_mode(
+            selected_command.get_tags_as_string(),
+            app,
+            &EditSelection::Tags,
+        ),
+        &selected_match.positions(Field::Tags),
+        match_style,
     ))
     .style(Style::default().fg(Color::Rgb(
         config.primary_color.unwrap().0,
@@ -341,10 +419,14 @@ fn render_commands<'a>(
             .border_type(BorderType::Plain),
     );
 
-    let description = Paragraph::new(coerce_string_by_mode(
-        selected_command.description,
-        app,
-        &EditSelection::Description,
+    let description = Paragraph::new(highlight_matches(
+        &coerce_string_by_mode(
+            selected_command.description,
+            app,
+            &EditSelection::Description,
+        ),
+        &selected_match.positions(Field::Description),
+        match_style,
     ))
     .style(Style::default().fg(Color::Rgb(
         config.primary_color.unwrap().0,
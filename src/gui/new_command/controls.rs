@@ -1,4 +1,3 @@
-use crate::core::trove::Trove;
 use crate::core::{string_to_tags, HoardCmd};
 use crate::gui::commands_gui::{DrawState, EditSelection, State};
 use termion::event::Key;
@@ -41,8 +40,11 @@ pub fn key_handler(input: Key, app: &mut State, default_namespace: &str) -> Opti
                         Ok(()) => String::new(),
                         Err(error) => error.to_string(),
                     };
-                    let trove = Trove::from_commands(&app.commands);
-                    if trove.get_command_collision(&command).is_some() {
+                    let is_colliding = app
+                        .commands
+                        .iter()
+                        .any(|c| c.namespace == command.namespace && c.name == command.name);
+                    if is_colliding {
                         msg = String::from(
                             "Command with that name already exists in another namespace",
                         );
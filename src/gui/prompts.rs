@@ -31,6 +31,23 @@ where
     }
 }
 
+/// Let the user deselect some of `options`, all are selected at first
+pub fn prompt_deselect_options<T, S>(selection_prompt: &str, options: &[T], text: S) -> Vec<T>
+where
+    T: Clone,
+    S: Fn(&T) -> String,
+{
+    let options_texts: Vec<String> = options.iter().map(text).collect();
+    let selected_indices = MultiSelect::with_theme(&HoardTheme::default())
+        .with_prompt(selection_prompt)
+        .items(&options_texts)
+        .defaults(&vec![true; options.len()])
+        .interact()
+        .unwrap();
+
+    take_elements_by_indices(options, &selected_indices)
+}
+
 pub fn prompt_yes_or_no(text: &str) -> Confirmation {
     const YES_ANSWER: usize = 0;
 
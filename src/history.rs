@@ -0,0 +1,310 @@
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fs};
+
+use crate::cli_commands::Shell;
+use crate::core::trove::Trove;
+
+/// Commands shorter than this are not worth hoarding ( `ls`, `cd ..`, ... )
+const MIN_COMMAND_LENGTH: usize = 8;
+
+/// A single command read from a shell history file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    pub command: String,
+    pub timestamp: Option<SystemTime>,
+}
+
+/// A command from the shell history which is suggested to be hoarded
+///
+/// A `HistoryCandidate` stores the following parameters
+/// - `command`: The command as it was typed into the shell
+/// - `count`: How often the command shows up in the history
+/// - `last_used`: When the command was last run, if the history records timestamps
+#[derive(Debug, Clone)]
+pub struct HistoryCandidate {
+    pub command: String,
+    pub count: usize,
+    pub last_used: Option<SystemTime>,
+}
+
+impl HistoryCandidate {
+    /// Frequently repeated commands rank highest, longer commands get a boost
+    /// as they are harder to remember and retype
+    #[allow(clippy::cast_precision_loss)]
+    fn score(&self) -> f64 {
+        self.count as f64 * (self.command.chars().count() as f64).sqrt()
+    }
+}
+
+/// Default location of the history file of `shell`
+pub fn default_history_path(shell: Shell) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    let path = match shell {
+        Shell::Bash => {
+            env::var("HISTFILE").map_or_else(|_| home.join(".bash_history"), PathBuf::from)
+        }
+        Shell::Zsh => {
+            env::var("HISTFILE").map_or_else(|_| home.join(".zsh_history"), PathBuf::from)
+        }
+        Shell::Fish => env::var("XDG_DATA_HOME")
+            .map_or_else(|_| home.join(".local/share"), PathBuf::from)
+            .join("fish/fish_history"),
+    };
+    Some(path)
+}
+
+/// Guess the shell of the user from `$SHELL`
+pub fn detect_shell() -> Option<Shell> {
+    let shell = env::var("SHELL").ok()?;
+    match Path::new(&shell).file_name()?.to_str()? {
+        "bash" => Some(Shell::Bash),
+        "zsh" => Some(Shell::Zsh),
+        "fish" => Some(Shell::Fish),
+        _ => None,
+    }
+}
+
+/// Read and parse the history file at `path` written by `shell`
+pub fn read_history_file(shell: Shell, path: &Path) -> Result<Vec<HistoryEntry>> {
+    if !path.exists() {
+        return Err(anyhow!("No history file found at {}", path.display()));
+    }
+    // zsh "metafies" non-ascii bytes, so the file is not always valid utf-8
+    let bytes = fs::read(path)?;
+    let content = String::from_utf8_lossy(&bytes);
+    Ok(parse_history(shell, &content))
+}
+
+pub fn parse_history(shell: Shell, content: &str) -> Vec<HistoryEntry> {
+    match shell {
+        Shell::Bash => parse_bash_history(content),
+        Shell::Zsh => parse_zsh_history(content),
+        Shell::Fish => parse_fish_history(content),
+    }
+}
+
+fn timestamp_from_secs(secs: &str) -> Option<SystemTime> {
+    secs.trim()
+        .parse::<u64>()
+        .ok()
+        .map(|s| UNIX_EPOCH + Duration::from_secs(s))
+}
+
+/// Parse `~/.bash_history`.
+/// If `HISTTIMEFORMAT` is set, bash writes a `#<unix timestamp>` line before every command
+fn parse_bash_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut timestamp = None;
+    for line in content.lines() {
+        if let Some(secs) = line.strip_prefix('#') {
+            if let Some(t) = timestamp_from_secs(secs) {
+                timestamp = Some(t);
+                continue;
+            }
+        }
+        if !line.trim().is_empty() {
+            entries.push(HistoryEntry {
+                command: line.trim().to_string(),
+                timestamp: timestamp.take(),
+            });
+        }
+    }
+    entries
+}
+
+/// Parse zsh history, both plain and in the `EXTENDED_HISTORY` format
+/// `: <start time>:<elapsed seconds>;<command>`
+/// Multiline commands are continued with a trailing `\`
+fn parse_zsh_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries = Vec::new();
+    let mut lines = content.lines();
+    while let Some(line) = lines.next() {
+        let (timestamp, mut command) = match line
+            .strip_prefix(": ")
+            .and_then(|rest| rest.split_once(';'))
+        {
+            Some((meta, command)) => (
+                meta.split(':').next().and_then(timestamp_from_secs),
+                command.to_string(),
+            ),
+            None => (None, line.to_string()),
+        };
+        while command.ends_with('\\') {
+            command.pop();
+            command.push('\n');
+            match lines.next() {
+                Some(next) => command.push_str(next),
+                None => break,
+            }
+        }
+        if !command.trim().is_empty() {
+            entries.push(HistoryEntry {
+                command: command.trim().to_string(),
+                timestamp,
+            });
+        }
+    }
+    entries
+}
+
+/// Parse fish history. It is *almost* YAML:
+/// ```text
+/// - cmd: git status
+///   when: 1700000000
+///   paths:
+///     - src
+/// ```
+fn parse_fish_history(content: &str) -> Vec<HistoryEntry> {
+    let mut entries: Vec<HistoryEntry> = Vec::new();
+    for line in content.lines() {
+        if let Some(command) = line.strip_prefix("- cmd: ") {
+            entries.push(HistoryEntry {
+                command: unescape_fish(command).trim().to_string(),
+                timestamp: None,
+            });
+        } else if let Some(when) = line.trim_start().strip_prefix("when: ") {
+            if let Some(entry) = entries.last_mut() {
+                entry.timestamp = timestamp_from_secs(when);
+            }
+        }
+    }
+    entries.retain(|e| !e.command.is_empty());
+    entries
+}
+
+fn unescape_fish(s: &str) -> String {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => unescaped.push('\n'),
+                Some(other) => unescaped.push(other),
+                None => unescaped.push('\\'),
+            }
+        } else {
+            unescaped.push(c);
+        }
+    }
+    unescaped
+}
+
+/// Group the history by command, drop short commands and commands already hoarded in `trove`,
+/// and return the `limit` best candidates ranked by frequency and length
+pub fn rank_commands(
+    entries: &[HistoryEntry],
+    trove: &Trove,
+    limit: usize,
+) -> Vec<HistoryCandidate> {
+    let mut grouped: HashMap<&str, HistoryCandidate> = HashMap::new();
+    for entry in entries {
+        let candidate = grouped
+            .entry(entry.command.as_str())
+            .or_insert_with(|| HistoryCandidate {
+                command: entry.command.clone(),
+                count: 0,
+                last_used: None,
+            });
+        candidate.count += 1;
+        candidate.last_used = candidate.last_used.max(entry.timestamp);
+    }
+
+    let mut candidates: Vec<HistoryCandidate> = grouped
+        .into_values()
+        .filter(|c| c.command.chars().count() >= MIN_COMMAND_LENGTH)
+        .filter(|c| !trove.commands.iter().any(|t| t.command == c.command))
+        .collect();
+
+    candidates.sort_by(|a, b| {
+        b.score()
+            .total_cmp(&a.score())
+            .then_with(|| b.last_used.cmp(&a.last_used))
+            .then_with(|| a.command.cmp(&b.command))
+    });
+    candidates.truncate(limit);
+    candidates
+}
+
+#[cfg(test)]
+mod test_history {
+    use super::*;
+    use crate::core::HoardCmd;
+
+    fn commands(entries: &[HistoryEntry]) -> Vec<&str> {
+        entries.iter().map(|e| e.command.as_str()).collect()
+    }
+
+    #[test]
+    fn parse_plain_bash_history() {
+        let entries = parse_history(Shell::Bash, "ls -la\n\ngit status\n");
+        assert_eq!(vec!["ls -la", "git status"], commands(&entries));
+        assert!(entries.iter().all(|e| e.timestamp.is_none()));
+    }
+
+    #[test]
+    fn parse_bash_history_with_timestamps() {
+        let entries = parse_history(Shell::Bash, "#1700000000\ngit status\n# a comment\n");
+        assert_eq!(vec!["git status", "# a comment"], commands(&entries));
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000)),
+            entries[0].timestamp
+        );
+        assert_eq!(None, entries[1].timestamp);
+    }
+
+    #[test]
+    fn parse_extended_zsh_history() {
+        let content = ": 1700000000:0;git status\n: 1700000005:2;echo foo \\\nbar\nplain command\n";
+        let entries = parse_history(Shell::Zsh, content);
+        assert_eq!(
+            vec!["git status", "echo foo \nbar", "plain command"],
+            commands(&entries)
+        );
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_005)),
+            entries[1].timestamp
+        );
+        assert_eq!(None, entries[2].timestamp);
+    }
+
+    #[test]
+    fn parse_fish_history_yaml() {
+        let content = "- cmd: git status\n  when: 1700000000\n- cmd: echo a\\\\nb\\nc\n  when: 1700000001\n  paths:\n    - src\n";
+        let entries = parse_history(Shell::Fish, content);
+        assert_eq!(vec!["git status", "echo a\\nb\nc"], commands(&entries));
+        assert_eq!(
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_001)),
+            entries[1].timestamp
+        );
+    }
+
+    #[test]
+    fn rank_by_frequency_and_skip_hoarded() {
+        let entries = parse_history(
+            Shell::Bash,
+            "ls\ncargo build --release\ngit log --oneline\ngit log --oneline\ndocker ps -a\n",
+        );
+        let mut trove = Trove::default();
+        let hoarded = HoardCmd::default()
+            .with_name("ps")
+            .with_namespace("docker")
+            .with_command("docker ps -a");
+        trove.add_command(hoarded, true).unwrap();
+
+        let candidates = rank_commands(&entries, &trove, 10);
+        let ranked: Vec<&str> = candidates.iter().map(|c| c.command.as_str()).collect();
+        assert_eq!(vec!["git log --oneline", "cargo build --release"], ranked);
+        assert_eq!(2, candidates[0].count);
+    }
+
+    #[test]
+    fn rank_respects_limit() {
+        let entries = parse_history(Shell::Bash, "cargo build\ncargo test --all\n");
+        let candidates = rank_commands(&entries, &Trove::default(), 1);
+        assert_eq!(1, candidates.len());
+        assert_eq!("cargo test --all", candidates[0].command);
+    }
+}
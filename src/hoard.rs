@@ -1,27 +1,53 @@
 use crate::cli_commands::{Cli, Commands};
+use anyhow::anyhow;
 use base64::engine::general_purpose;
-use clap::Parser;
-use dotenv::dotenv;
+use chrono::{DateTime, Local};
+use clap::CommandFactory;
 use log::info;
-use reqwest::{StatusCode, Url};
+use reqwest::StatusCode;
 use std::fs;
+use std::io::{self, IsTerminal};
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use url::ParseError;
+use std::time::{Duration, SystemTime};
 
-use crate::cli_commands::Mode;
-use crate::config::HoardConfig;
+use crate::cli_commands::{
+    ConflictStrategy, Format, ListFormat, Mode, NamespaceAction, SelectionArgs, Shell,
+    SubscriptionAction, TagAction,
+};
+use crate::clipboard;
+use crate::completions;
 use crate::config::{load_or_build_config, save_hoard_config_file, save_parameter_token};
+use crate::config::{HoardConfig, HOARD_CONFIG};
+use crate::core::bulk::{
+    add_tag, find_command, merge_namespaces, move_to_namespace, remove_tag, rename_namespace,
+    rename_tag, Change,
+};
+use crate::core::edit;
+use crate::core::prune::{prunable, prune, Criteria};
+use crate::core::ranking::Sort;
 use crate::core::trove::Trove;
 use crate::core::HoardCmd;
-use crate::filter::query_trove;
-use crate::gui::commands_gui;
+use crate::filter::{query_trove, select_commands, CommandSelection};
+use crate::formats::into_namespace;
+use crate::formats::just::{trove_from_just, trove_to_just};
+use crate::formats::list::{to_json, to_json_lines, to_names, to_tsv};
+use crate::formats::make::trove_from_make;
+use crate::formats::navi::{trove_from_navi, trove_to_navi};
+use crate::formats::pet::{trove_from_pet, trove_to_pet};
+use crate::formats::shell::trove_to_shell_functions;
+use crate::formats::show::{self, Source};
+use crate::gui::commands_gui::{self, Outcome};
 use crate::gui::prompts::{
-    prompt_input, prompt_multiselect_options, prompt_password, prompt_password_repeat,
-    prompt_yes_or_no, Confirmation,
+    prompt_deselect_options, prompt_input, prompt_multiselect_options, prompt_password,
+    prompt_password_repeat, prompt_yes_or_no, Confirmation,
 };
+use crate::history::{default_history_path, detect_shell, rank_commands, read_history_file};
+use crate::query::Query;
+use crate::stats::Stats;
+use crate::subscriptions::{load_subscribed_troves, subscription_of};
 use crate::sync_models::TokenResponse;
-use crate::util::rem_first_and_last;
+use crate::util::{edit_file, read_from_uri, rem_first_and_last};
 use base64::Engine as _;
 #[derive(Default, Debug)]
 pub struct Hoard {
@@ -30,9 +56,14 @@ pub struct Hoard {
 }
 
 impl Hoard {
-    pub fn with_config(&mut self, hoard_home_path: Option<String>) -> &mut Self {
+    /// Load the config from `hoard_dir` and use the trove file at `trove_path`, if they are set
+    pub fn with_config(
+        &mut self,
+        hoard_dir: Option<PathBuf>,
+        trove_path: Option<PathBuf>,
+    ) -> &mut Self {
         info!("Loading config");
-        match load_or_build_config(hoard_home_path) {
+        match load_or_build_config(hoard_dir, trove_path) {
             Ok(config) => self.config = config,
             Err(err) => {
                 eprintln!("ERROR: {err}");
@@ -45,10 +76,9 @@ impl Hoard {
         self
     }
 
-    pub fn start(&mut self) -> (String, bool) {
-        dotenv().ok();
+    #[allow(clippy::too_many_lines)]
+    pub fn start(&mut self, cli: &Cli) -> (String, bool) {
         let mut autocomplete_command = String::new();
-        let cli = Cli::parse();
 
         match &cli.command {
             Commands::Info {} => {
@@ -59,27 +89,74 @@ impl Hoard {
                 tags,
                 command,
                 description,
+                namespace,
+                favorite,
+                yes,
+                from_stdin,
             } => {
-                self.new_command(
-                    name.clone(),
-                    tags.clone(),
-                    command.clone(),
-                    description.clone(),
-                );
+                let command = if *from_stdin {
+                    match io::read_to_string(io::stdin()) {
+                        Ok(stdin) => Some(stdin.trim_end_matches(['\n', '\r']).to_string()),
+                        Err(e) => {
+                            eprintln!("Could not read the command from stdin: {e}");
+                            std::process::exit(1);
+                        }
+                    }
+                } else {
+                    command.clone()
+                };
+                if *yes || *from_stdin {
+                    self.new_command_without_prompts(
+                        name.clone(),
+                        tags.as_deref(),
+                        command.as_deref().unwrap_or_default(),
+                        description.clone(),
+                        namespace.clone(),
+                        *favorite,
+                    );
+                } else {
+                    self.new_command(
+                        name.clone(),
+                        tags.clone(),
+                        command,
+                        description.clone(),
+                        namespace.clone(),
+                        *favorite,
+                    );
+                }
             }
             Commands::List {
                 filter,
+                format,
                 json,
+                sort,
+                order,
                 simple,
+                copy,
             } => {
-                let commands =
-                    self.list_commands(simple.to_owned(), json.to_owned(), filter.clone());
+                let sort = sort.map(|field| {
+                    let sort = Sort::new(field);
+                    order.map_or(sort, |order| sort.with_order(order))
+                });
+                let format = format
+                    .or_else(|| json.then_some(ListFormat::Json))
+                    .or_else(|| simple.then_some(ListFormat::Table));
+                let commands = self.list_commands(format, filter.as_deref(), sort, *copy);
                 if let Some(c) = commands {
                     autocomplete_command = c;
                 }
             }
-            Commands::Pick { name } => {
-                self.pick_command(name);
+            Commands::Pick { name, copy } => {
+                self.pick_command(name, *copy);
+            }
+            Commands::Show {
+                name,
+                namespace,
+                json,
+            } => {
+                if let Some(details) = self.show_command(name, namespace.as_deref(), *json) {
+                    autocomplete_command = details;
+                }
             }
             Commands::Remove { name } => {
                 self.remove_command(name);
@@ -90,20 +167,112 @@ impl Hoard {
             Commands::SetParameterToken { name } => {
                 self.set_parameter_token(name);
             }
-            Commands::Import { uri } => {
-                self.import_trove(uri);
+            Commands::Import {
+                uri,
+                format,
+                namespace,
+                on_conflict,
+                dry_run,
+                json,
+            } => {
+                if let Some(report) = self.import_trove(
+                    uri,
+                    *format,
+                    namespace.as_deref(),
+                    *on_conflict,
+                    *dry_run,
+                    *json,
+                ) {
+                    autocomplete_command = report;
+                }
             }
-            Commands::Export { path } => {
-                self.export_command(path);
+            Commands::ImportHistory { shell, path, limit } => {
+                self.import_history(*shell, path.clone(), *limit);
             }
-            Commands::Edit { name } => {
-                self.edit_command(name);
+            Commands::Export {
+                path,
+                format,
+                all,
+                selection,
+                stdout: _,
+            } => match CommandSelection::try_from(selection) {
+                Ok(selection) => {
+                    if let Some(exported) =
+                        self.export_command(path.as_deref(), *format, *all, &selection)
+                    {
+                        autocomplete_command = exported;
+                    }
+                }
+                Err(e) => eprintln!("{e}"),
+            },
+            Commands::Edit {
+                name,
+                editor,
+                selection,
+            } => {
+                if *editor {
+                    let mut selection = selection.clone();
+                    selection.extend(name.iter().cloned());
+                    self.edit_in_editor(&selection);
+                } else if let Some(name) = name {
+                    self.edit_command(name);
+                }
             }
             Commands::ShellConfig { shell } => {
                 Self::shell_config_command(shell);
             }
-            Commands::Sync { command } => {
-                self.sync(*command);
+            Commands::Sync {
+                command,
+                on_conflict,
+            } => {
+                self.sync(*command, *on_conflict);
+            }
+            Commands::Subscriptions { command } => {
+                self.subscriptions(*command);
+            }
+            Commands::Stats {
+                top,
+                stale_after,
+                filter,
+                json,
+            } => {
+                if let Some(stats) = self.stats(*top, *stale_after, filter.as_deref(), *json) {
+                    autocomplete_command = stats;
+                }
+            }
+            Commands::Completions {
+                shell,
+                complete,
+                words,
+            } => {
+                if *complete {
+                    self.complete(words);
+                } else if let Some(shell) = shell {
+                    autocomplete_command = completions::script(&mut Cli::command(), *shell);
+                }
+            }
+            Commands::Tag { action } => self.tag_commands(action),
+            Commands::Namespace { action } => self.namespaces_command(action),
+            Commands::Mv {
+                arguments,
+                selection,
+                dry_run,
+            } => self.move_commands(arguments, selection, *dry_run),
+            Commands::Prune {
+                unused_for,
+                never_used,
+                duplicates,
+                near_duplicates,
+                yes,
+                dry_run,
+            } => {
+                let criteria = Criteria {
+                    unused_for: *unused_for,
+                    never_used: *never_used,
+                    duplicates: *duplicates,
+                    near_duplicates: *near_duplicates,
+                };
+                self.prune_commands(&criteria, *yes, *dry_run);
             }
         }
 
@@ -130,61 +299,166 @@ impl Hoard {
         tags: Option<String>,
         command: Option<String>,
         description: Option<String>,
+        namespace: Option<String>,
+        is_favorite: bool,
     ) {
         let trove_namespaces = self.trove.namespaces();
         //trove_namespaces.push(&default_ns_clone);
-        let new_command = HoardCmd::default()
-            .with_command_string_input(
-                command,
-                &self.config.parameter_token.clone().unwrap(),
-                &self.config.parameter_ending_token.clone().unwrap(),
-            )
-            .with_namespace_input(&trove_namespaces)
-            .with_name_input(name, &self.trove)
-            .with_description_input(description.unwrap_or_default())
-            .with_tags_input(tags);
+        let new_command = HoardCmd::default().with_command_string_input(
+            command,
+            &self.config.parameter_token.clone().unwrap(),
+            &self.config.parameter_ending_token.clone().unwrap(),
+        );
+        let new_command = match namespace {
+            Some(namespace) => new_command.with_namespace(&namespace),
+            None => new_command.with_namespace_input(&trove_namespaces),
+        };
+        let new_command = HoardCmd {
+            is_favorite,
+            ..new_command
+                .with_name_input(name, &self.trove)
+                .with_description_input(description.unwrap_or_default())
+                .with_tags_input(tags)
+        };
         let _ = self.trove.add_command(new_command, true);
         self.save_trove(None);
     }
 
+    /// `hoard new --yes`: Hoard a command from flags only, for scripts.
+    /// Invalid fields are printed to stderr and end hoard with exit code 1
+    fn new_command_without_prompts(
+        &mut self,
+        name: Option<String>,
+        tags: Option<&str>,
+        command: &str,
+        description: Option<String>,
+        namespace: Option<String>,
+        is_favorite: bool,
+    ) {
+        let new_command = HoardCmd::default().with_command(command).with_fields(
+            name,
+            namespace.unwrap_or_else(|| self.config.default_namespace.clone()),
+            description,
+            tags,
+            &self.trove,
+        );
+        let new_command = match new_command {
+            Ok(new_command) => HoardCmd {
+                is_favorite,
+                ..new_command
+            },
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        };
+        let name = new_command.name.clone();
+        let namespace = new_command.namespace.clone();
+        if let Err(e) = self.trove.add_command(new_command, false) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        self.save_trove(None);
+        eprintln!("Hoarded [{name}] in namespace [{namespace}]");
+    }
+
     fn list_commands(
         &mut self,
-        is_simple: bool,
-        is_structured: bool,
-        filter: Option<String>,
+        format: Option<ListFormat>,
+        filter: Option<&str>,
+        sort: Option<Sort>,
+        copy: bool,
     ) -> Option<String> {
-        if self.trove.is_empty() {
+        let mut trove = self.trove_with_subscriptions();
+        let Some(format) = format else {
+            return self.pick_in_gui(trove, sort, copy);
+        };
+        // Every format lists the commands matching `filter`, best matches first unless sorted
+        let query = match Query::parse(filter.unwrap_or_default()) {
+            Ok(query) => query,
+            Err(e) => {
+                eprintln!("{e}");
+                return None;
+            }
+        };
+        let is_nothing_hoarded = trove.is_empty();
+        trove = query_trove(&trove, &query);
+        if let Some(sort) = sort {
+            sort.apply(&mut trove.commands);
+        }
+        let parameter_token = self.config.parameter_token.as_deref().unwrap_or_default();
+        let parameter_ending_token = self
+            .config
+            .parameter_ending_token
+            .as_deref()
+            .unwrap_or_default();
+        match format {
+            ListFormat::Json => Some(to_json(
+                &trove.commands,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            ListFormat::Jsonl => Some(to_json_lines(
+                &trove.commands,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            ListFormat::Yaml => Some(trove.to_yaml()),
+            ListFormat::Tsv => Some(to_tsv(&trove.commands)),
+            ListFormat::Names => Some(to_names(&trove.commands)),
+            ListFormat::Table => {
+                if is_nothing_hoarded {
+                    println!("No command hoarded.\nRun [ hoard new ] first to hoard a command.");
+                } else {
+                    trove.print_trove();
+                }
+                None
+            }
+        }
+    }
+
+    /// Let the user pick a command in the interactive list. Returns the command to put into the shell,
+    /// or copies it to the clipboard with `copy`
+    fn pick_in_gui(&mut self, mut trove: Trove, sort: Option<Sort>, copy: bool) -> Option<String> {
+        if trove.is_empty() {
             println!("No command hoarded.\nRun [ hoard new ] first to hoard a command.");
-        } else if is_simple {
-            self.trove.print_trove();
-        } else if is_structured {
-            // Return list of commands in json format, filtered by `filter`
-            let query_string: String = filter.unwrap_or_default();
-            let filtered_trove = query_trove(&self.trove, &query_string);
-            return Some(filtered_trove.to_yaml());
-        } else {
-            match commands_gui::run(&mut self.trove, &self.config) {
+            return None;
+        }
+        loop {
+            let gui_result = commands_gui::run(&mut trove, &self.config, sort);
+            self.trove = trove.without_read_only_commands();
+            match gui_result {
+                Ok(Some(Outcome::EditInEditor(c))) => {
+                    self.save_trove(None);
+                    self.edit_in_editor(&[format!("{}/{}", c.namespace, c.name)]);
+                    trove = self.trove_with_subscriptions();
+                }
                 Ok(selected_command) => {
                     self.save_trove(None);
-                    if let Some(c) = selected_command {
+                    if let Some(Outcome::Selected(c)) = selected_command {
                         // Is set if a command is selected in GUI
-                        if !c.command.is_empty() {
-                            //TODO: If run as cli program, copy command into clipboard, else will be written to READLINE_LINE
+                        if copy {
+                            Self::copy_command(&c);
+                        } else if !c.command.is_empty() {
                             return Some(c.command);
                         }
                     }
+                    return None;
                 }
                 Err(e) => {
                     println!("{e}");
+                    return None;
                 }
             }
         }
-        None
     }
 
-    fn pick_command(&mut self, name: &str) {
-        let command_result = self.trove.pick_command(&self.config, name);
+    fn pick_command(&self, name: &str, copy: bool) {
+        let command_result = self
+            .trove_with_subscriptions()
+            .pick_command(&self.config, name);
         match command_result {
+            Ok(c) if copy => Self::copy_command(&c),
             Ok(c) => {
                 println!("{}", c.command);
             }
@@ -192,6 +466,82 @@ impl Hoard {
         }
     }
 
+    fn copy_command(command: &HoardCmd) {
+        match clipboard::copy(&command.command) {
+            Ok(()) => eprintln!("Copied [{}] to the clipboard", command.name),
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+        }
+    }
+
+    fn show_command(&self, name: &str, namespace: Option<&str>, json: bool) -> Option<String> {
+        let trove = self.trove_with_subscriptions();
+        let named: Vec<&HoardCmd> = trove
+            .commands
+            .iter()
+            .filter(|c| c.name == name && namespace.is_none_or(|ns| c.namespace == ns))
+            .collect();
+        let command = match named.as_slice() {
+            [command] => *command,
+            [] => {
+                match namespace {
+                    Some(namespace) => eprintln!("No command [{name}] in namespace [{namespace}]"),
+                    None => eprintln!("No command [{name}] in the trove"),
+                }
+                return None;
+            }
+            _ => {
+                let namespaces: Vec<&str> = named.iter().map(|c| c.namespace.as_str()).collect();
+                eprintln!(
+                    "Several commands are named [{name}], choose one with --namespace: {}",
+                    namespaces.join(", ")
+                );
+                return None;
+            }
+        };
+
+        let subscription = self
+            .config
+            .config_home_path
+            .as_deref()
+            .filter(|_| command.is_read_only)
+            .and_then(|hoard_dir| {
+                let subscriptions = self.config.subscriptions.as_deref().unwrap_or_default();
+                subscription_of(subscriptions, hoard_dir, command)
+            });
+        let source = subscription.map_or_else(
+            || Source::Trove {
+                path: self.config.trove_path.clone().unwrap_or_default(),
+            },
+            |subscription| Source::Subscription {
+                uri: subscription.uri.clone(),
+            },
+        );
+        let parameter_token = self.config.parameter_token.as_deref().unwrap_or_default();
+        let parameter_ending_token = self
+            .config
+            .parameter_ending_token
+            .as_deref()
+            .unwrap_or_default();
+        if json {
+            Some(show::to_json(
+                command,
+                &source,
+                parameter_token,
+                parameter_ending_token,
+            ))
+        } else {
+            Some(show::to_text(
+                command,
+                &source,
+                parameter_token,
+                parameter_ending_token,
+            ))
+        }
+    }
+
     fn remove_command(&mut self, command_name: &str) {
         let command_result = self.trove.remove_command(command_name);
         match command_result {
@@ -203,6 +553,191 @@ impl Hoard {
         self.save_trove(None);
     }
 
+    /// Run a bulk operation on your own commands and print what it changed.
+    /// With `dry_run` the changes are only printed, nothing is saved
+    fn change_commands<F>(&mut self, dry_run: bool, change: F)
+    where
+        F: FnOnce(&mut Trove) -> anyhow::Result<Vec<Change>>,
+    {
+        let mut trove = self.trove.clone();
+        let changes = match change(&mut trove) {
+            Ok(changes) => changes,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+        for change in &changes {
+            println!("{change}");
+        }
+        if changes.is_empty() {
+            println!("Nothing to change");
+        } else if dry_run {
+            println!(
+                "Dry run: {} commands would change, nothing was saved",
+                changes.len()
+            );
+        } else {
+            self.trove = trove;
+            self.save_trove(None);
+            println!("Changed {} commands", changes.len());
+        }
+    }
+
+    fn tag_commands(&mut self, action: &TagAction) {
+        let (TagAction::Add { selection, .. }
+        | TagAction::Remove { selection, .. }
+        | TagAction::Rename { selection, .. }) = action;
+        let selection = match CommandSelection::try_from(selection) {
+            Ok(selection) => selection,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+        match action {
+            TagAction::Add { name, dry_run, .. } => {
+                if selection.is_empty() {
+                    eprintln!("Select the commands to tag with --namespace, --tag, --filter or --favorites");
+                    return;
+                }
+                self.change_commands(*dry_run, |trove| add_tag(trove, &selection, name));
+            }
+            TagAction::Remove { name, dry_run, .. } => {
+                self.change_commands(*dry_run, |trove| Ok(remove_tag(trove, &selection, name)));
+            }
+            TagAction::Rename {
+                old, new, dry_run, ..
+            } => {
+                self.change_commands(*dry_run, |trove| rename_tag(trove, &selection, old, new));
+            }
+        }
+    }
+
+    fn namespaces_command(&mut self, action: &NamespaceAction) {
+        match action {
+            NamespaceAction::List => {
+                let trove = self.trove_with_subscriptions();
+                let namespaces = trove.namespaces();
+                let width = namespaces
+                    .iter()
+                    .map(|ns| ns.len())
+                    .max()
+                    .unwrap_or_default();
+                for namespace in namespaces {
+                    let commands: Vec<&HoardCmd> = trove
+                        .commands
+                        .iter()
+                        .filter(|c| c.namespace == namespace)
+                        .collect();
+                    let read_only = if commands.iter().all(|c| c.is_read_only) {
+                        " (subscribed, read-only)"
+                    } else {
+                        ""
+                    };
+                    println!("{namespace:<width$}  {}{read_only}", commands.len());
+                }
+            }
+            NamespaceAction::Rename { old, new, dry_run } => {
+                self.change_commands(*dry_run, |trove| rename_namespace(trove, old, new));
+            }
+            NamespaceAction::Merge {
+                namespaces,
+                into,
+                dry_run,
+            } => {
+                self.change_commands(*dry_run, |trove| merge_namespaces(trove, namespaces, into));
+            }
+        }
+    }
+
+    /// `hoard mv`: `arguments` are the commands to move followed by the namespace to move them into
+    fn move_commands(&mut self, arguments: &[String], selection: &SelectionArgs, dry_run: bool) {
+        let Some((target, commands)) = arguments.split_last() else {
+            return;
+        };
+        let selection = match CommandSelection::try_from(selection) {
+            Ok(selection) => selection,
+            Err(e) => {
+                eprintln!("{e}");
+                return;
+            }
+        };
+        if commands.is_empty() && selection.is_empty() {
+            eprintln!("Name the commands to move, or select them with --namespace, --tag, --filter or --favorites");
+            return;
+        }
+        let mut named = Vec::new();
+        for reference in commands {
+            match find_command(&self.trove, reference) {
+                Ok(command) => named.push((command.namespace.clone(), command.name.clone())),
+                Err(e) => {
+                    eprintln!("{e}");
+                    return;
+                }
+            }
+        }
+        let is_selected = |command: &HoardCmd| {
+            named
+                .iter()
+                .any(|(namespace, name)| command.namespace == *namespace && command.name == *name)
+                || (!selection.is_empty() && selection.matches(command))
+        };
+        self.change_commands(dry_run, |trove| {
+            move_to_namespace(trove, is_selected, target)
+        });
+    }
+
+    /// Remove the commands matching `criteria` after the user confirmed them, or right away with `yes`
+    fn prune_commands(&mut self, criteria: &Criteria, yes: bool, dry_run: bool) {
+        let prunable = prunable(&self.trove, criteria, SystemTime::now());
+        if prunable.is_empty() {
+            println!("Nothing to prune");
+            return;
+        }
+        if dry_run {
+            for command in &prunable {
+                println!("{command}");
+            }
+            println!(
+                "Dry run: {} commands would be pruned, nothing was saved",
+                prunable.len()
+            );
+            return;
+        }
+        let pruned = if yes {
+            prunable
+        } else if io::stdin().is_terminal() {
+            prompt_deselect_options(
+                "Commands to prune ( Space to keep a command, Enter to prune the selected ones )",
+                &prunable,
+                ToString::to_string,
+            )
+        } else {
+            eprintln!(
+                "Add --yes to prune {} commands without confirming them",
+                prunable.len()
+            );
+            std::process::exit(1);
+        };
+        if pruned.is_empty() {
+            println!("Nothing pruned");
+            return;
+        }
+
+        let mut trove = self.trove.clone();
+        if let Err(e) = prune(&mut trove, &pruned) {
+            eprintln!("{e}");
+            return;
+        }
+        self.trove = trove;
+        self.save_trove(None);
+        for command in &pruned {
+            println!("Removed {command}");
+        }
+        println!("Pruned {} commands", pruned.len());
+    }
+
     fn remove_namespace(&mut self, namespace: &str) {
         let command_result = self.trove.remove_namespace_commands(namespace);
         match command_result {
@@ -214,75 +749,259 @@ impl Hoard {
         self.save_trove(None);
     }
 
-    fn import_trove(&mut self, path: &str) {
-        match Url::parse(path) {
-            Ok(url) => match reqwest_trove(url) {
-                Ok(trove_string) => {
-                    let imported_trove = Trove::load_trove_from_string(&trove_string[..]);
-                    self.trove.merge_trove(&imported_trove);
-                    self.save_trove(None);
-                }
-                Err(e) => {
-                    println!("Could not import trove from url: {e}");
-                }
-            },
-            Err(err) => {
-                if err == ParseError::RelativeUrlWithoutBase {
-                    let imported_trove = Trove::load_trove_file(&Some(PathBuf::from(path)));
-                    self.trove.merge_trove(&imported_trove);
-                    self.save_trove(None);
-                } else {
-                    eprintln!("Not a valid URL or file path");
+    fn import_trove(
+        &mut self,
+        path: &str,
+        format: Format,
+        namespace: Option<&str>,
+        on_conflict: ConflictStrategy,
+        dry_run: bool,
+        json: bool,
+    ) -> Option<String> {
+        let content = match read_from_uri(path) {
+            Ok(content) => content,
+            Err(e) => {
+                eprintln!("Could not import {path}: {e}");
+                return None;
+            }
+        };
+        let parameter_token = self.config.parameter_token.as_ref().unwrap();
+        let parameter_ending_token = self.config.parameter_ending_token.as_ref().unwrap();
+        let default_namespace = namespace.unwrap_or(&self.config.default_namespace);
+        let imported = match format {
+            Format::Trove => Ok((Trove::load_trove_from_string(&content), Vec::new())),
+            Format::Navi => Ok((
+                trove_from_navi(
+                    &content,
+                    default_namespace,
+                    parameter_token,
+                    parameter_ending_token,
+                ),
+                Vec::new(),
+            )),
+            Format::Pet => trove_from_pet(
+                &content,
+                default_namespace,
+                parameter_token,
+                parameter_ending_token,
+            )
+            .map_err(|e| anyhow!("The supplied pet snippet file is invalid!\n{e}")),
+            Format::Just => Ok(trove_from_just(
+                &content,
+                default_namespace,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            Format::Make => Ok(trove_from_make(
+                &content,
+                default_namespace,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            Format::Bash | Format::Zsh | Format::FishFunctions => {
+                Err(anyhow!("Shell functions can only be exported"))
+            }
+        };
+        let imported_trove = match imported {
+            Ok((trove, warnings)) => {
+                for warning in warnings {
+                    eprintln!("WARNING: {warning}");
                 }
+                trove
             }
+            Err(e) => {
+                eprintln!("{e}");
+                return None;
+            }
+        };
+        let imported_trove = match namespace {
+            Some(namespace) => into_namespace(&imported_trove, namespace),
+            None => imported_trove,
+        };
+        if dry_run {
+            let diff = self.trove.diff_trove(&imported_trove, on_conflict);
+            return Some(if json {
+                diff.to_json()
+            } else {
+                diff.to_string()
+            });
         }
+        self.trove.merge_trove(&imported_trove, on_conflict);
+        self.save_trove(None);
+        None
     }
 
-    fn export_command(&self, path: &str) {
-        let target_path = PathBuf::from(path);
-        if target_path.file_name().is_some() {
-            let namespaces = self.trove.namespaces();
-
-            let selected_namespaces = prompt_multiselect_options(
-                "Export specific namespaces?",
-                "Namespaces to export ( Space to select )",
-                &namespaces,
-                |namespace| *namespace,
+    fn import_history(&mut self, shell: Option<Shell>, path: Option<String>, limit: usize) {
+        let Some(shell) = shell.or_else(detect_shell) else {
+            eprintln!(
+                "Could not detect your shell. Set it with [ hoard import-history --shell <SHELL> ]"
             );
-
-            if selected_namespaces.is_empty() {
-                println!("Nothing selected");
+            return;
+        };
+        let Some(history_path) = path
+            .map(PathBuf::from)
+            .or_else(|| default_history_path(shell))
+        else {
+            eprintln!("Could not find your history file. Set it with [ hoard import-history --path <PATH> ]");
+            return;
+        };
+        let entries = match read_history_file(shell, &history_path) {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("{e}");
                 return;
             }
+        };
+
+        let candidates = rank_commands(&entries, &self.trove, limit);
+        if candidates.is_empty() {
+            println!("No new commands found in {}", history_path.display());
+            return;
+        }
 
-            let commands = self
+        let selected_candidates = prompt_multiselect_options(
+            "Hoard specific commands from your history?",
+            "Commands to hoard ( Space to select )",
+            &candidates,
+            |candidate| format!("[{}x] {}", candidate.count, candidate.command),
+        );
+
+        if selected_candidates.is_empty() {
+            println!("Nothing selected");
+            return;
+        }
+
+        for candidate in selected_candidates {
+            let trove_namespaces: Vec<String> = self
                 .trove
-                .commands
-                .iter()
-                .filter(|command| selected_namespaces.contains(&command.namespace.as_str()))
-                .collect::<Vec<_>>();
-
-            let selected_commands = prompt_multiselect_options(
-                "Export specific commands?",
-                "Commands to export ( Space to select )",
-                &commands,
-                |command| command.name.as_str(),
-            );
+                .namespaces()
+                .into_iter()
+                .map(ToString::to_string)
+                .collect();
+            let trove_namespaces: Vec<&str> = trove_namespaces.iter().map(String::as_str).collect();
+            let new_command = HoardCmd::default()
+                .with_command_string_input(
+                    Some(candidate.command),
+                    &self.config.parameter_token.clone().unwrap(),
+                    &self.config.parameter_ending_token.clone().unwrap(),
+                )
+                .with_namespace_input(&trove_namespaces)
+                .with_name_input(None, &self.trove)
+                .with_description_input(String::new())
+                .with_tags_input(None);
+            let _ = self.trove.add_command(new_command, true);
+        }
+        self.save_trove(None);
+    }
 
-            if selected_commands.is_empty() {
-                println!("Nothing selected");
-                return;
+    fn export_command(
+        &self,
+        path: Option<&str>,
+        format: Format,
+        all: bool,
+        selection: &CommandSelection,
+    ) -> Option<String> {
+        let target_path = path.map(PathBuf::from);
+        if target_path
+            .as_ref()
+            .is_some_and(|p| p.file_name().is_none())
+        {
+            println!("No valid path with filename provided.");
+            return None;
+        }
+
+        // Only ask which commands to export if nothing is selected by flags and someone is there to answer
+        let trove_for_export = if !all && selection.is_empty() && io::stdin().is_terminal() {
+            self.prompt_export_selection()?
+        } else {
+            select_commands(&self.trove, selection)
+        };
+
+        if trove_for_export.is_empty() {
+            eprintln!("Nothing selected");
+            return None;
+        }
+
+        let parameter_token = self.config.parameter_token.as_ref().unwrap();
+        let parameter_ending_token = self.config.parameter_ending_token.as_ref().unwrap();
+        let exported = match format {
+            Format::Trove => Ok(trove_for_export.to_yaml()),
+            Format::Navi => Ok(trove_to_navi(
+                &trove_for_export,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            Format::Pet => trove_to_pet(&trove_for_export, parameter_token, parameter_ending_token),
+            Format::Just => Ok(trove_to_just(
+                &trove_for_export,
+                parameter_token,
+                parameter_ending_token,
+            )),
+            Format::Make => Err(anyhow!(
+                "Makefiles can only be imported. Export a justfile with --format just instead"
+            )),
+            Format::Bash | Format::Zsh | Format::FishFunctions => {
+                let (functions, warnings) = trove_to_shell_functions(
+                    &trove_for_export,
+                    format.shell().expect("shell function format"),
+                    parameter_token,
+                    parameter_ending_token,
+                );
+                for warning in warnings {
+                    eprintln!("WARNING: {warning}");
+                }
+                Ok(functions)
             }
+        };
 
-            let mut trove_for_export = Trove::default();
-            for command in selected_commands {
-                let _ = trove_for_export.add_command(command.clone(), true);
+        match target_path {
+            // Written to stdout
+            None => match exported {
+                Ok(content) => return Some(content),
+                Err(e) => eprintln!("Could not export trove: {e}"),
+            },
+            Some(target_path) => {
+                if let Err(e) = exported.and_then(|content| Ok(fs::write(&target_path, content)?)) {
+                    eprintln!("Could not write {}: {e}", target_path.display());
+                }
             }
+        }
+        None
+    }
 
-            trove_for_export.save_trove_file(&target_path);
-        } else {
-            println!("No valid path with filename provided.");
+    fn prompt_export_selection(&self) -> Option<Trove> {
+        let namespaces = self.trove.namespaces();
+
+        let selected_namespaces = prompt_multiselect_options(
+            "Export specific namespaces?",
+            "Namespaces to export ( Space to select )",
+            &namespaces,
+            |namespace| *namespace,
+        );
+
+        if selected_namespaces.is_empty() {
+            return None;
         }
+
+        let commands = self
+            .trove
+            .commands
+            .iter()
+            .filter(|command| selected_namespaces.contains(&command.namespace.as_str()))
+            .collect::<Vec<_>>();
+
+        let selected_commands = prompt_multiselect_options(
+            "Export specific commands?",
+            "Commands to export ( Space to select )",
+            &commands,
+            |command| command.name.as_str(),
+        );
+
+        let mut trove_for_export = Trove::default();
+        for command in selected_commands {
+            let _ = trove_for_export.add_command(command.clone(), true);
+        }
+        Some(trove_for_export)
     }
 
     pub fn set_parameter_token(&self, parameter_token: &str) {
@@ -319,6 +1038,81 @@ impl Hoard {
         }
     }
 
+    /// `hoard edit --editor`: edit the `selection` as YAML in your editor and apply the changes.
+    /// The editor opens again with the errors as comments until the document is valid or closed without saving
+    fn edit_in_editor(&mut self, selection: &[String]) {
+        let mut selected: Vec<HoardCmd> = Vec::new();
+        for reference in selection {
+            let commands: Vec<&HoardCmd> = if self.trove.namespaces().contains(&reference.as_str())
+            {
+                self.trove
+                    .commands
+                    .iter()
+                    .filter(|c| c.namespace == *reference)
+                    .collect()
+            } else {
+                match find_command(&self.trove, reference) {
+                    Ok(command) => vec![command],
+                    Err(e) => {
+                        eprintln!("{e}");
+                        return;
+                    }
+                }
+            };
+            for command in commands {
+                if !selected
+                    .iter()
+                    .any(|c| c.namespace == command.namespace && c.name == command.name)
+                {
+                    selected.push(command.clone());
+                }
+            }
+        }
+        if selection.is_empty() {
+            selected.clone_from(&self.trove.commands);
+        }
+        let selected: Vec<&HoardCmd> = selected.iter().collect();
+
+        // A new file with a random name, so no other user can make hoard write somewhere else
+        let file = match tempfile::Builder::new()
+            .prefix("hoard-edit-")
+            .suffix(".yml")
+            .tempfile()
+        {
+            Ok(file) => file,
+            Err(e) => {
+                eprintln!("Could not create a temporary file: {e}");
+                return;
+            }
+        };
+        let path = file.path();
+        let mut document = edit::to_document(&selected);
+        let result = loop {
+            if let Err(e) = fs::write(path, &document) {
+                break Err(anyhow!("Could not write {}: {e}", path.display()));
+            }
+            if let Err(e) = edit_file(path) {
+                break Err(e);
+            }
+            let edited = match fs::read_to_string(path) {
+                Ok(edited) => edited,
+                Err(e) => break Err(anyhow!("Could not read {}: {e}", path.display())),
+            };
+            if edited == document {
+                break Ok(None);
+            }
+            match edit::edits(&self.trove, &selected, &edited) {
+                Ok(edits) => break Ok(Some(edits)),
+                Err(errors) => document = edit::with_error_comments(&edited, &errors),
+            }
+        };
+        match result {
+            Ok(Some(edits)) => self.change_commands(false, |trove| Ok(edit::apply(trove, &edits))),
+            Ok(None) => println!("Nothing to change"),
+            Err(e) => eprintln!("{e}"),
+        }
+    }
+
     fn shell_config_command(shell: &str) {
         let src = match shell {
             "bash" => include_str!("shell/hoard.bash"),
@@ -332,6 +1126,106 @@ impl Hoard {
         print!("{src}");
     }
 
+    fn stats(
+        &self,
+        top: usize,
+        stale_after: Duration,
+        filter: Option<&str>,
+        json: bool,
+    ) -> Option<String> {
+        let query = match Query::parse(filter.unwrap_or_default()) {
+            Ok(query) => query,
+            Err(e) => {
+                eprintln!("{e}");
+                return None;
+            }
+        };
+        let trove = query_trove(&self.trove, &query);
+        let stats = Stats::of(&trove.commands, SystemTime::now(), top, stale_after);
+        if json {
+            Some(stats.to_json())
+        } else {
+            Some(stats.to_tables())
+        }
+    }
+
+    /// Print the values to complete for the last of `words` from your trove and the cached subscribed troves.
+    /// Exits with code 1 if there is nothing hoard can complete, so the shell completes it
+    fn complete(&self, words: &[String]) {
+        let mut trove = self.trove.clone();
+        if let Some(hoard_dir) = self.config.config_home_path.as_deref() {
+            for subscription in self.config.subscriptions.as_deref().unwrap_or_default() {
+                trove = trove.with_read_only_commands(&subscription.load(hoard_dir));
+            }
+        }
+        let mut cli = Cli::command();
+        cli.build();
+        let Some(values) = completions::complete(&cli, words, &trove) else {
+            std::process::exit(1);
+        };
+        for value in values {
+            println!("{value}");
+        }
+        // Without the line `main` prints for every command
+        std::process::exit(0);
+    }
+
+    /// Your own commands together with the read-only commands of all subscribed troves.
+    /// Subscriptions whose refresh interval has passed are fetched again
+    fn trove_with_subscriptions(&self) -> Trove {
+        let subscriptions = self.config.subscriptions.as_deref().unwrap_or_default();
+        match self.config.config_home_path.as_deref() {
+            Some(hoard_dir) if !subscriptions.is_empty() => self
+                .trove
+                .clone()
+                .with_read_only_commands(&load_subscribed_troves(subscriptions, hoard_dir)),
+            _ => self.trove.clone(),
+        }
+    }
+
+    fn subscriptions(&self, action: SubscriptionAction) {
+        let subscriptions = self.config.subscriptions.clone().unwrap_or_default();
+        let Some(hoard_dir) = self.config.config_home_path.as_deref() else {
+            return;
+        };
+        if subscriptions.is_empty() {
+            println!(
+                "No subscriptions yet.\nAdd them to `subscriptions` in {}",
+                hoard_dir.join(HOARD_CONFIG).display()
+            );
+            return;
+        }
+        match action {
+            SubscriptionAction::Update => {
+                for subscription in &subscriptions {
+                    match subscription.update(hoard_dir) {
+                        Ok(count) => println!("Fetched {count} commands from {}", subscription.uri),
+                        Err(e) => eprintln!("Could not update {}: {e}", subscription.uri),
+                    }
+                }
+            }
+            SubscriptionAction::List => {
+                for subscription in &subscriptions {
+                    let fetched_at = subscription.fetched_at(hoard_dir).map_or_else(
+                        || "never fetched".to_string(),
+                        |time| {
+                            format!(
+                                "fetched {}",
+                                DateTime::<Local>::from(time).format("%Y-%m-%d %H:%M")
+                            )
+                        },
+                    );
+                    println!(
+                        "{} -> {}/ (refreshed every {}h, {fetched_at})",
+                        subscription.uri,
+                        subscription.namespace_prefix,
+                        subscription.refresh_interval_hours
+                    );
+                }
+            }
+        }
+    }
+
     pub fn load_trove(&mut self) -> &mut Self {
         self.trove = Trove::load_trove_file(&self.config.trove_path);
         self
@@ -459,7 +1353,7 @@ impl Hoard {
         }
     }
 
-    pub fn sync(&mut self, command: Mode) {
+    pub fn sync(&mut self, command: Mode, on_conflict: ConflictStrategy) {
         // Check if user is logged in
         // Else inform the user to run `hoard sync login` first and break
         match command {
@@ -498,7 +1392,7 @@ impl Hoard {
                 if let Some(t) = trove {
                     // Prepare backup trove to enable reverting if merge goes all wrong, or user incorrectly removes commands they wanted to keep
                     self.save_backup_trove(None);
-                    let was_dirty = self.trove.merge_trove(&t);
+                    let was_dirty = self.trove.merge_trove(&t, on_conflict);
                     if was_dirty {
                         self.save_trove(None);
                         println!("All done!");
@@ -519,9 +1413,3 @@ impl Hoard {
         self.config.api_token.is_some()
     }
 }
-
-#[tokio::main]
-async fn reqwest_trove(url: Url) -> Result<String, Box<dyn std::error::Error>> {
-    let resp = reqwest::get(url).await?.text().await?;
-    Ok(resp)
-}
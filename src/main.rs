@@ -10,22 +10,36 @@ extern crate rand;
 extern crate array_tool;
 
 mod cli_commands;
+mod clipboard;
+mod completions;
 mod config;
 mod core;
 mod filter;
+mod formats;
+mod fuzzy;
 mod gpt;
 mod gui;
+mod history;
 mod hoard;
+mod query;
+mod search_index;
+mod stats;
+mod subscriptions;
 mod sync_models;
 mod util;
+use clap::Parser;
+use cli_commands::Cli;
 use hoard::Hoard;
 
 #[tokio::main]
 async fn main() {
+    // `.env` may set `HOARD_HOME` and `HOARD_TROVE` too
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
     let (command, is_autocomplete) = Hoard::default()
-        .with_config(None)
+        .with_config(cli.config.clone(), cli.trove.clone())
         .load_trove()
-        .start();
+        .start(&cli);
     if is_autocomplete {
         eprintln!("{}", command.trim());
     } else {
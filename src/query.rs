@@ -0,0 +1,623 @@
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+use crate::core::HoardCmd;
+use crate::filter::{match_fields, CommandMatch, Field, FieldTexts};
+use crate::fuzzy::Pattern;
+
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+
+/// A search query, like `ns:git tag:remote -tag:deprecated push "force push" used:>5 modified:<30d fav:true`
+///
+/// A command matches the query if it matches all of its terms
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+/// A single term of a query. Negated terms start with `-` and select commands not matching the predicate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub is_negated: bool,
+    pub predicate: Predicate,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// Plain words, or `name:`, `cmd:` and `desc:` to only look at one field.
+    /// Quoted text has to appear as is, everything else is matched fuzzy
+    Text {
+        field: Option<Field>,
+        text: String,
+        is_exact: bool,
+    },
+    /// `ns:<namespace>`, the namespace is compared ignoring case
+    Namespace(String),
+    /// `tag:<tag>`, the command has this tag, ignoring case
+    Tag(String),
+    /// `used:>5`, compares how often a command was selected
+    UsageCount(Comparison, usize),
+    /// `created:`, `modified:` or `last_used:` with a comparison and an age, like `modified:<30d`.
+    /// `<30d` means less than 30 days ago
+    Age(Timestamp, Comparison, Duration),
+    /// `fav:true` or `fav:false`
+    Favorite(bool),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+
+impl Comparison {
+    fn compare<T: Ord>(self, value: &T, other: &T) -> bool {
+        match self {
+            Self::Less => value < other,
+            Self::LessOrEqual => value <= other,
+            Self::Equal => value == other,
+            Self::GreaterOrEqual => value >= other,
+            Self::Greater => value > other,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Timestamp {
+    Created,
+    Modified,
+    LastUsed,
+}
+
+impl Timestamp {
+    const fn of(self, command: &HoardCmd) -> SystemTime {
+        match self {
+            Self::Created => command.created,
+            Self::Modified => command.modified,
+            Self::LastUsed => command.last_used,
+        }
+    }
+}
+
+/// A query that could not be parsed. `position` is the index of the character where the problem starts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl QueryError {
+    fn new(message: &str, position: usize) -> Self {
+        Self {
+            message: message.to_string(),
+            position,
+        }
+    }
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid query at character {}: {}",
+            self.position + 1,
+            self.message
+        )
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+/// The value of a term, after the qualifier
+struct Value {
+    text: String,
+    is_quoted: bool,
+    position: usize,
+}
+
+/// Reads terms from the characters of a query
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.index += 1;
+        }
+    }
+
+    /// Read up to the next whitespace
+    fn read_word(&mut self) -> String {
+        let start = self.index;
+        while self.peek().is_some_and(|c| !c.is_whitespace()) {
+            self.index += 1;
+        }
+        self.chars[start..self.index].iter().collect()
+    }
+
+    /// Read `"quoted text"` or a word
+    fn read_value(&mut self) -> Result<Value, QueryError> {
+        let position = self.index;
+        if self.peek() != Some('"') {
+            return Ok(Value {
+                text: self.read_word(),
+                is_quoted: false,
+                position,
+            });
+        }
+        self.index += 1;
+        let start = self.index;
+        while self.peek().is_some_and(|c| c != '"') {
+            self.index += 1;
+        }
+        if self.peek().is_none() {
+            return Err(QueryError::new("Missing closing '\"'", position));
+        }
+        let text = self.chars[start..self.index].iter().collect();
+        self.index += 1;
+        Ok(Value {
+            text,
+            is_quoted: true,
+            position,
+        })
+    }
+
+    /// Read `qualifier:` if the next word starts with a known qualifier
+    fn read_qualifier(&mut self) -> Option<String> {
+        let start = self.index;
+        let mut end = start;
+        while self
+            .chars
+            .get(end)
+            .is_some_and(|c| c.is_alphanumeric() || *c == '_')
+        {
+            end += 1;
+        }
+        if self.chars.get(end) != Some(&':') {
+            return None;
+        }
+        let qualifier: String = self.chars[start..end].iter().collect();
+        let qualifier = qualifier.to_lowercase();
+        if !QUALIFIERS.contains(&qualifier.as_str()) {
+            return None;
+        }
+        self.index = end + 1;
+        Some(qualifier)
+    }
+
+    fn read_term(&mut self) -> Result<Term, QueryError> {
+        let start = self.index;
+        let is_negated = self.peek() == Some('-');
+        if is_negated {
+            self.index += 1;
+            if self.peek().is_none_or(char::is_whitespace) {
+                return Err(QueryError::new(
+                    "Expected a term to exclude after '-'",
+                    start,
+                ));
+            }
+        }
+        let qualifier = self.read_qualifier();
+        let value = self.read_value()?;
+        let predicate = match qualifier {
+            None => Predicate::Text {
+                field: None,
+                text: value.text,
+                is_exact: value.is_quoted,
+            },
+            Some(qualifier) => to_predicate(&qualifier, value)?,
+        };
+        Ok(Term {
+            is_negated,
+            predicate,
+        })
+    }
+}
+
+/// Qualifiers of field terms, like `ns` in `ns:git`. Words with other qualifiers are matched as text
+const QUALIFIERS: [&str; 16] = [
+    "name",
+    "ns",
+    "namespace",
+    "tag",
+    "tags",
+    "cmd",
+    "command",
+    "desc",
+    "description",
+    "used",
+    "created",
+    "modified",
+    "last_used",
+    "lastused",
+    "fav",
+    "favorite",
+];
+
+fn to_predicate(qualifier: &str, value: Value) -> Result<Predicate, QueryError> {
+    if value.text.is_empty() {
+        return Err(QueryError::new(
+            &format!("Missing value after '{qualifier}:'"),
+            value.position,
+        ));
+    }
+    let text_in = |field| Predicate::Text {
+        field: Some(field),
+        text: value.text.clone(),
+        is_exact: value.is_quoted,
+    };
+    let predicate = match qualifier {
+        "name" => text_in(Field::Name),
+        "cmd" | "command" => text_in(Field::Command),
+        "desc" | "description" => text_in(Field::Description),
+        "ns" | "namespace" => Predicate::Namespace(value.text),
+        "tag" | "tags" => Predicate::Tag(value.text),
+        "used" => {
+            let (comparison, count) = split_comparison(&value.text, Comparison::Equal);
+            let count = count.parse().map_err(|_| {
+                QueryError::new(
+                    &format!("Expected a number of uses like 'used:>5', got '{count}'"),
+                    value.position,
+                )
+            })?;
+            Predicate::UsageCount(comparison, count)
+        }
+        "created" | "modified" | "last_used" | "lastused" => {
+            let timestamp = match qualifier {
+                "created" => Timestamp::Created,
+                "modified" => Timestamp::Modified,
+                _ => Timestamp::LastUsed,
+            };
+            // `modified:30d` means within the last 30 days
+            let (comparison, age) = split_comparison(&value.text, Comparison::LessOrEqual);
+            let age = parse_age(age).ok_or_else(|| {
+                QueryError::new(
+                    &format!("Expected an age like '30d', '12h', '2w' or '1y', got '{age}'"),
+                    value.position,
+                )
+            })?;
+            Predicate::Age(timestamp, comparison, age)
+        }
+        _ => match value.text.to_lowercase().as_str() {
+            "true" | "yes" => Predicate::Favorite(true),
+            "false" | "no" => Predicate::Favorite(false),
+            _ => {
+                return Err(QueryError::new(
+                    &format!("Expected 'true' or 'false', got '{}'", value.text),
+                    value.position,
+                ))
+            }
+        },
+    };
+    Ok(predicate)
+}
+
+/// Split a leading comparison operator like `>=` from `text`
+fn split_comparison(text: &str, default: Comparison) -> (Comparison, &str) {
+    [
+        (">=", Comparison::GreaterOrEqual),
+        ("<=", Comparison::LessOrEqual),
+        (">", Comparison::Greater),
+        ("<", Comparison::Less),
+        ("=", Comparison::Equal),
+    ]
+    .into_iter()
+    .find_map(|(operator, comparison)| text.strip_prefix(operator).map(|rest| (comparison, rest)))
+    .unwrap_or((default, text))
+}
+
+/// Parse an age like `12h`, `30d`, `2w` or `1y`
+pub fn parse_age(text: &str) -> Option<Duration> {
+    let unit = text.chars().last()?;
+    let amount: u64 = text[..text.len() - unit.len_utf8()].parse().ok()?;
+    let hours = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        'y' => 24 * 365,
+        _ => return None,
+    };
+    Some(Duration::from_secs(
+        amount.checked_mul(hours * SECONDS_PER_HOUR)?,
+    ))
+}
+
+impl Query {
+    /// Parse a query. Terms are separated by whitespace:
+    ///
+    /// - `push`: Fuzzy matches any field, `"git push"` has to appear as is
+    /// - `name:`, `cmd:`, `desc:`: Match the text in one field only
+    /// - `ns:git`, `tag:remote`: The command is in the namespace or has the tag
+    /// - `used:>5`: Compare the usage count with `<`, `<=`, `=`, `>=` or `>`
+    /// - `created:`, `modified:`, `last_used:`: Compare the age with units `h`, `d`, `w` or `y`, like `modified:<30d`
+    /// - `fav:true`: Only favorites, `fav:false` no favorites
+    /// - `-` in front of a term excludes matching commands, like `-tag:deprecated`
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            index: 0,
+        };
+        let mut terms = Vec::new();
+        loop {
+            parser.skip_whitespace();
+            if parser.peek().is_none() {
+                return Ok(Self { terms });
+            }
+            terms.push(parser.read_term()?);
+        }
+    }
+
+    /// Match `command` against all terms. The score and positions come from the text terms
+    pub fn matches(&self, command: &HoardCmd) -> Option<CommandMatch> {
+        self.matcher().matches(command, &FieldTexts::of(command))
+    }
+
+    /// The query prepared to match many commands
+    pub fn matcher(&self) -> Matcher<'_> {
+        let terms = self
+            .terms
+            .iter()
+            .map(|term| {
+                let pattern = match &term.predicate {
+                    Predicate::Text {
+                        text,
+                        is_exact: true,
+                        ..
+                    } => Some(Pattern::exact(text)),
+                    Predicate::Text { text, .. } => Some(Pattern::fuzzy(text)),
+                    _ => None,
+                };
+                (term, pattern)
+            })
+            .collect();
+        Matcher { terms }
+    }
+}
+
+/// A query with the patterns of its text terms prepared, see [`Query::matcher`]
+pub struct Matcher<'a> {
+    terms: Vec<(&'a Term, Option<Pattern>)>,
+}
+
+impl Matcher<'_> {
+    /// Signature of the characters every matching command contains, see [`crate::fuzzy::signature`]
+    pub fn signature(&self) -> u64 {
+        self.terms
+            .iter()
+            .filter(|(term, _)| !term.is_negated)
+            .filter_map(|(_, pattern)| pattern.as_ref().map(Pattern::signature))
+            .fold(0, |a, b| a | b)
+    }
+
+    /// Match a command with its field texts against all terms, like [`Query::matches`]
+    pub fn matches(&self, command: &HoardCmd, texts: &FieldTexts) -> Option<CommandMatch> {
+        self.match_terms(command, texts, true)
+    }
+
+    /// Score of a command with its field texts, without collecting the matched positions
+    pub fn score(&self, command: &HoardCmd, texts: &FieldTexts) -> Option<i64> {
+        self.match_terms(command, texts, false).map(|m| m.score)
+    }
+
+    fn match_terms(
+        &self,
+        command: &HoardCmd,
+        texts: &FieldTexts,
+        with_positions: bool,
+    ) -> Option<CommandMatch> {
+        let mut command_match = CommandMatch::default();
+        for (term, pattern) in &self.terms {
+            let matched = match (&term.predicate, pattern) {
+                (Predicate::Text { field, .. }, Some(pattern)) => {
+                    let fields = field.as_ref().map_or(&Field::ALL[..], std::slice::from_ref);
+                    match_fields(texts, fields, pattern, with_positions)
+                }
+                (predicate, _) => predicate.matches(command),
+            };
+            match (matched, term.is_negated) {
+                (Some(matched), false) => command_match.extend(matched),
+                (None, true) => (),
+                _ => return None,
+            }
+        }
+        Some(command_match)
+    }
+}
+
+impl Predicate {
+    /// Match all predicates but texts, which need a prepared pattern
+    fn matches(&self, command: &HoardCmd) -> Option<CommandMatch> {
+        match self {
+            Self::Text { .. } => None,
+            Self::Namespace(namespace) => {
+                command.namespace.eq_ignore_ascii_case(namespace).then(|| {
+                    let length = command.namespace.chars().count();
+                    CommandMatch::new(Field::Namespace, 0, (0..length).collect())
+                })
+            }
+            Self::Tag(tag) => {
+                let index = command
+                    .tags
+                    .iter()
+                    .position(|t| t.eq_ignore_ascii_case(tag))?;
+                // Position of the tag in the comma separated tags
+                let start: usize = command.tags[..index]
+                    .iter()
+                    .map(|t| t.chars().count() + 1)
+                    .sum();
+                let end = start + command.tags[index].chars().count();
+                Some(CommandMatch::new(Field::Tags, 0, (start..end).collect()))
+            }
+            Self::UsageCount(comparison, count) => comparison
+                .compare(&command.usage_count, count)
+                .then(CommandMatch::default),
+            Self::Age(timestamp, comparison, age) => {
+                let elapsed = SystemTime::now()
+                    .duration_since(timestamp.of(command))
+                    .unwrap_or_default();
+                comparison
+                    .compare(&elapsed, age)
+                    .then(CommandMatch::default)
+            }
+            Self::Favorite(is_favorite) => {
+                (command.is_favorite == *is_favorite).then(CommandMatch::default)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_query {
+    use super::*;
+
+    fn text(text: &str, is_exact: bool) -> Predicate {
+        Predicate::Text {
+            field: None,
+            text: text.to_string(),
+            is_exact,
+        }
+    }
+
+    fn term(predicate: Predicate) -> Term {
+        Term {
+            is_negated: false,
+            predicate,
+        }
+    }
+
+    fn parse_error(query: &str) -> QueryError {
+        Query::parse(query).unwrap_err()
+    }
+
+    #[test]
+    fn parse_full_query() {
+        let query = Query::parse(
+            r#"ns:git tag:remote name:push -tag:deprecated "exact phrase" used:>5 modified:<30d fav:true"#,
+        )
+        .unwrap();
+        assert_eq!(
+            vec![
+                term(Predicate::Namespace("git".to_string())),
+                term(Predicate::Tag("remote".to_string())),
+                term(Predicate::Text {
+                    field: Some(Field::Name),
+                    text: "push".to_string(),
+                    is_exact: false,
+                }),
+                Term {
+                    is_negated: true,
+                    predicate: Predicate::Tag("deprecated".to_string()),
+                },
+                term(text("exact phrase", true)),
+                term(Predicate::UsageCount(Comparison::Greater, 5)),
+                term(Predicate::Age(
+                    Timestamp::Modified,
+                    Comparison::Less,
+                    Duration::from_secs(30 * 24 * SECONDS_PER_HOUR)
+                )),
+                term(Predicate::Favorite(true)),
+            ],
+            query.terms
+        );
+    }
+
+    #[test]
+    fn parse_plain_words() {
+        let query = Query::parse("  docker  http://localhost:8080 ").unwrap();
+        assert_eq!(
+            vec![
+                term(text("docker", false)),
+                term(text("http://localhost:8080", false)),
+            ],
+            query.terms
+        );
+        assert!(Query::parse("").unwrap().terms.is_empty());
+    }
+
+    #[test]
+    fn parse_quoted_values_and_defaults() {
+        let query = Query::parse(r#"desc:"two words" -"rm -rf" used:3 last_used:2w"#).unwrap();
+        assert_eq!(
+            vec![
+                term(Predicate::Text {
+                    field: Some(Field::Description),
+                    text: "two words".to_string(),
+                    is_exact: true,
+                }),
+                Term {
+                    is_negated: true,
+                    predicate: text("rm -rf", true),
+                },
+                term(Predicate::UsageCount(Comparison::Equal, 3)),
+                term(Predicate::Age(
+                    Timestamp::LastUsed,
+                    Comparison::LessOrEqual,
+                    Duration::from_secs(14 * 24 * SECONDS_PER_HOUR)
+                )),
+            ],
+            query.terms
+        );
+    }
+
+    #[test]
+    fn syntax_errors() {
+        assert_eq!(
+            QueryError::new("Missing closing '\"'", 4),
+            parse_error("git \"push")
+        );
+        assert_eq!(3, parse_error("ns: git").position);
+        assert_eq!(5, parse_error("used:many").position);
+        assert_eq!(9, parse_error("modified:<30 days").position);
+        assert_eq!(4, parse_error("fav:maybe").position);
+        assert_eq!(4, parse_error("git - push").position);
+        assert_eq!(
+            "Invalid query at character 5: Expected 'true' or 'false', got 'maybe'",
+            parse_error("fav:maybe").to_string()
+        );
+    }
+
+    #[test]
+    fn evaluate_query() {
+        let mut push = HoardCmd::default()
+            .with_name("push")
+            .with_namespace("git")
+            .with_command("git push --force")
+            .with_tags_raw("vcs,remote");
+        push.usage_count = 7;
+        let matches = |query: &str| Query::parse(query).unwrap().matches(&push).is_some();
+
+        assert!(matches(
+            "ns:GIT tag:remote name:psh used:>5 modified:<1d fav:false"
+        ));
+        assert!(matches(r#""push --force" -tag:deprecated"#));
+        assert!(!matches("ns:gi"));
+        assert!(!matches("-tag:vcs"));
+        assert!(!matches(r#""force push""#));
+        assert!(!matches("used:<=5"));
+        assert!(!matches("created:>1d"));
+        assert!(!matches("fav:true"));
+    }
+
+    #[test]
+    fn positions_of_field_terms() {
+        let command = HoardCmd::default()
+            .with_name("push")
+            .with_namespace("git")
+            .with_tags_raw("vcs,remote");
+        let matched = Query::parse("tag:remote ns:git")
+            .unwrap()
+            .matches(&command)
+            .unwrap();
+        assert_eq!(vec![4, 5, 6, 7, 8, 9], matched.positions(Field::Tags));
+        assert_eq!(vec![0, 1, 2], matched.positions(Field::Namespace));
+    }
+}
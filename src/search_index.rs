@@ -0,0 +1,214 @@
+use std::cmp::{Ordering, Reverse};
+use std::rc::Rc;
+use std::time::SystemTime;
+
+use crate::core::context::UsageContext;
+use crate::core::ranking::{compare, rank, Ranking};
+use crate::core::HoardCmd;
+use crate::filter::FieldTexts;
+use crate::query::Query;
+
+/// A command with everything the search needs prepared.
+/// The command is shared with the list on screen, so showing search results doesn't copy commands
+struct Entry {
+    command: Rc<HoardCmd>,
+    texts: FieldTexts<'static>,
+}
+
+impl Entry {
+    fn new(command: HoardCmd) -> Self {
+        let texts = FieldTexts::of(&command).into_owned();
+        Self {
+            command: Rc::new(command),
+            texts,
+        }
+    }
+}
+
+/// Commands of a TUI session prepared for searching as you type.
+///
+/// Field texts and character signatures are built once. A query scans all commands, but only runs the matcher
+/// on commands containing all characters of its text terms. Commands are kept in ranked order: added and
+/// edited commands are put in place and deleted ones taken out, without ranking all commands again
+pub struct SearchIndex {
+    /// Indexed commands, best ranked first
+    entries: Vec<Entry>,
+    ranking: Ranking,
+    context: Option<UsageContext>,
+    /// When the commands were ranked, so commands put in place later are ranked the same way
+    ranked_at: SystemTime,
+}
+
+impl SearchIndex {
+    pub fn new(commands: &[HoardCmd], ranking: Ranking, context: Option<UsageContext>) -> Self {
+        let ranked_at = SystemTime::now();
+        let mut entries: Vec<Entry> = commands.iter().cloned().map(Entry::new).collect();
+        rank(
+            &mut entries,
+            |entry| &entry.command,
+            ranking,
+            context.as_ref(),
+        );
+        Self {
+            entries,
+            ranking,
+            context,
+            ranked_at,
+        }
+    }
+
+    /// Put `entry` after all commands ranked before or equal to it, like a command added to the end of the trove
+    fn insert_ranked(&mut self, entry: Entry) {
+        let position = self.entries.partition_point(|other| {
+            compare(
+                &other.command,
+                &entry.command,
+                self.ranking,
+                self.context.as_ref(),
+                self.ranked_at,
+            ) != Ordering::Greater
+        });
+        self.entries.insert(position, entry);
+    }
+
+    /// All commands, best ranked first
+    pub fn commands(&self) -> impl Iterator<Item = &Rc<HoardCmd>> {
+        self.entries.iter().map(|entry| &entry.command)
+    }
+
+    /// Add `command`, replacing a command with the same name and namespace.
+    /// Mirrors [`crate::core::trove::Trove::add_command`] once the trove added it
+    pub fn insert(&mut self, command: HoardCmd) {
+        self.entries.retain(|entry| {
+            entry.command.name != command.name || entry.command.namespace != command.namespace
+        });
+        self.insert_ranked(Entry::new(command));
+    }
+
+    /// Remove all commands named `name`, like [`crate::core::trove::Trove::remove_command`]
+    pub fn remove(&mut self, name: &str) {
+        self.entries.retain(|entry| entry.command.name != name);
+    }
+
+    /// Replace all editable commands named like `command`, like [`crate::core::trove::Trove::update_command_by_name`]
+    pub fn update_by_name(&mut self, command: &HoardCmd) {
+        let count = self.entries.len();
+        self.entries
+            .retain(|entry| entry.command.name != command.name || entry.command.is_read_only);
+        for _ in self.entries.len()..count {
+            self.insert_ranked(Entry::new(command.clone()));
+        }
+    }
+
+    /// Search all commands accepted by `filter` for `query`.
+    /// Best matches come first, equally good matches in ranked order.
+    /// The matched positions are left out to save time, [`Query::matches`] finds them for the commands on screen
+    pub fn search<F>(&self, query: &Query, filter: F) -> Vec<&Rc<HoardCmd>>
+    where
+        F: Fn(&HoardCmd) -> bool,
+    {
+        let matcher = query.matcher();
+        let required = matcher.signature();
+        let mut scored: Vec<(&Rc<HoardCmd>, i64)> = self
+            .entries
+            .iter()
+            .filter(|entry| {
+                entry.texts.signature() & required == required && filter(&entry.command)
+            })
+            .filter_map(|entry| {
+                matcher
+                    .score(&entry.command, &entry.texts)
+                    .map(|score| (&entry.command, score))
+            })
+            .collect();
+        scored.sort_by_key(|(_, score)| Reverse(*score));
+        scored.into_iter().map(|(command, _)| command).collect()
+    }
+}
+
+#[cfg(test)]
+mod test_search_index {
+    use super::*;
+
+    fn names(index: &SearchIndex, query: &str) -> Vec<String> {
+        index
+            .search(&Query::parse(query).unwrap(), |_| true)
+            .into_iter()
+            .map(|c| c.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn search_like_the_query() {
+        let command = |namespace: &str, name: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace(namespace)
+                .with_command(&format!("{namespace} {name}"))
+        };
+        let commands = vec![
+            command("git", "stash"),
+            command("git", "status").with_usage(1, SystemTime::now()),
+            command("docker", "ps"),
+        ];
+        let index = SearchIndex::new(&commands, Ranking::Usage, None);
+        assert_eq!(vec!["status", "stash", "ps"], names(&index, ""));
+        assert_eq!(vec!["status", "stash"], names(&index, "ns:git st"));
+        assert_eq!(vec!["stash"], names(&index, "\"git stash\""));
+        assert!(names(&index, "kubectl").is_empty());
+
+        let docker_only = index.search(&Query::default(), |c| c.namespace == "docker");
+        assert_eq!(1, docker_only.len());
+    }
+
+    #[test]
+    fn incremental_updates() {
+        let git = |name: &str, command: &str| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace("git")
+                .with_command(command)
+        };
+        let mut index =
+            SearchIndex::new(&[git("status", "git status")], Ranking::Alphabetical, None);
+        index.insert(git("log", "git log"));
+        assert_eq!(vec!["log", "status"], names(&index, ""));
+
+        index.insert(git("log", "git log --graph"));
+        assert_eq!(vec!["log"], names(&index, "graph"));
+        assert_eq!(2, index.commands().count());
+
+        index.update_by_name(&git("log", "git log --oneline"));
+        assert_eq!(vec!["log"], names(&index, "oneline"));
+
+        index.remove("status");
+        assert_eq!(1, index.commands().count());
+        assert_eq!(vec!["log"], names(&index, ""));
+    }
+
+    #[test]
+    fn updates_keep_the_ranked_order() {
+        let used = |name: &str, uses: usize| {
+            HoardCmd::default()
+                .with_name(name)
+                .with_namespace("git")
+                .with_command(&format!("git {name}"))
+                .with_usage(uses, SystemTime::now())
+        };
+        let mut index = SearchIndex::new(
+            &[used("status", 9), used("log", 3), used("push", 1)],
+            Ranking::Usage,
+            None,
+        );
+        index.insert(used("fetch", 5));
+        index.insert(used("stash", 3));
+        assert_eq!(
+            vec!["status", "fetch", "log", "stash", "push"],
+            names(&index, "")
+        );
+
+        index.update_by_name(&used("push", 10));
+        index.remove("fetch");
+        assert_eq!(vec!["push", "status", "log", "stash"], names(&index, ""));
+    }
+}
@@ -0,0 +1,275 @@
+use chrono::{DateTime, Local};
+use prettytable::{format, Table};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, SystemTime};
+
+use crate::core::HoardCmd;
+use crate::formats::list::to_rfc3339;
+
+/// Number of commands in a namespace, with a tag or created in a month
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Count {
+    pub name: String,
+    pub commands: usize,
+}
+
+/// How often and how recently a command was used
+#[derive(Debug, Serialize)]
+pub struct CommandUsage {
+    pub namespace: String,
+    pub name: String,
+    pub usage_count: usize,
+    pub created: String,
+    /// `None` if the command was never used
+    pub last_used: Option<String>,
+}
+
+impl From<&HoardCmd> for CommandUsage {
+    fn from(command: &HoardCmd) -> Self {
+        Self {
+            namespace: command.namespace.clone(),
+            name: command.name.clone(),
+            usage_count: command.usage_count,
+            created: to_rfc3339(command.created),
+            last_used: (command.usage_count > 0).then(|| to_rfc3339(command.last_used)),
+        }
+    }
+}
+
+/// `hoard stats`: what the trove records about the use of its commands
+///
+/// - `favorite_ratio`: Share of favorite commands, from 0 to 1
+/// - `stale`: Commands used before, but not within `stale_after_days`, least recently used first
+/// - `created_per_month`: Months as `YYYY-MM` in local time, oldest first
+#[derive(Debug, Serialize)]
+pub struct Stats {
+    pub commands: usize,
+    pub favorites: usize,
+    pub favorite_ratio: f64,
+    pub namespaces: Vec<Count>,
+    pub tags: Vec<Count>,
+    pub most_used: Vec<CommandUsage>,
+    pub never_used: Vec<CommandUsage>,
+    pub stale_after_days: u64,
+    pub stale: Vec<CommandUsage>,
+    pub created_per_month: Vec<Count>,
+}
+
+/// Counts of the keys, most commands first, then by name
+fn counts<'a>(keys: impl Iterator<Item = &'a str>) -> Vec<Count> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for key in keys {
+        *counts.entry(key).or_default() += 1;
+    }
+    let mut counts: Vec<Count> = counts
+        .into_iter()
+        .map(|(name, commands)| Count {
+            name: name.to_string(),
+            commands,
+        })
+        .collect();
+    counts.sort_by_key(|count| Reverse(count.commands));
+    counts
+}
+
+impl Stats {
+    /// Statistics of `commands` at the time `now`.
+    /// `top` limits the most used commands, commands not used for `stale_after` are stale
+    #[allow(clippy::cast_precision_loss)]
+    pub fn of(commands: &[HoardCmd], now: SystemTime, top: usize, stale_after: Duration) -> Self {
+        let favorites = commands.iter().filter(|c| c.is_favorite).count();
+        let favorite_ratio = if commands.is_empty() {
+            0.0
+        } else {
+            favorites as f64 / commands.len() as f64
+        };
+
+        let mut most_used: Vec<&HoardCmd> = commands.iter().filter(|c| c.usage_count > 0).collect();
+        most_used.sort_by_key(|c| Reverse((c.usage_count, c.last_used)));
+        most_used.truncate(top);
+
+        let mut never_used: Vec<&HoardCmd> =
+            commands.iter().filter(|c| c.usage_count == 0).collect();
+        never_used.sort_by_key(|c| c.created);
+
+        let stale_since = now
+            .checked_sub(stale_after)
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        let mut stale: Vec<&HoardCmd> = commands
+            .iter()
+            .filter(|c| c.usage_count > 0 && c.last_used < stale_since)
+            .collect();
+        stale.sort_by_key(|c| c.last_used);
+
+        let months: Vec<String> = commands
+            .iter()
+            .map(|c| {
+                DateTime::<Local>::from(c.created)
+                    .format("%Y-%m")
+                    .to_string()
+            })
+            .collect();
+        let mut created_per_month = counts(months.iter().map(String::as_str));
+        created_per_month.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Self {
+            commands: commands.len(),
+            favorites,
+            favorite_ratio,
+            namespaces: counts(commands.iter().map(|c| c.namespace.as_str())),
+            tags: counts(commands.iter().flat_map(|c| &c.tags).map(String::as_str)),
+            most_used: most_used.into_iter().map(CommandUsage::from).collect(),
+            never_used: never_used.into_iter().map(CommandUsage::from).collect(),
+            stale_after_days: stale_after.as_secs() / (24 * 60 * 60),
+            stale: stale.into_iter().map(CommandUsage::from).collect(),
+            created_per_month,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("statistics can always be written as JSON")
+    }
+
+    /// One table per statistic, with a title line each
+    pub fn to_tables(&self) -> String {
+        let mut text = String::new();
+        let _ = writeln!(
+            text,
+            "Commands: {}, favorites: {} ({:.1}%)",
+            self.commands,
+            self.favorites,
+            self.favorite_ratio * 100.0
+        );
+        let count_table = |title: &str, header: &str, counts: &[Count]| {
+            let mut table = new_table(&[header, "Commands"]);
+            for count in counts {
+                table.add_row(row![count.name, r->count.commands]);
+            }
+            format!("\n{title}\n{table}")
+        };
+        let usage_table = |title: &str, usages: &[CommandUsage]| {
+            let mut table = new_table(&["Name", "Namespace", "Uses", "Created", "Last used"]);
+            for usage in usages {
+                table.add_row(row![
+                    usage.name,
+                    usage.namespace,
+                    r->usage.usage_count,
+                    local_date(&usage.created),
+                    usage.last_used.as_deref().map_or_else(|| String::from("never"), local_date)
+                ]);
+            }
+            format!("\n{title}\n{table}")
+        };
+        text.push_str(&count_table("Namespaces", "Namespace", &self.namespaces));
+        text.push_str(&count_table("Tags", "Tag", &self.tags));
+        text.push_str(&usage_table(
+            &format!("Most used, top {}", self.most_used.len()),
+            &self.most_used,
+        ));
+        text.push_str(&usage_table(
+            &format!("Never used: {}", self.never_used.len()),
+            &self.never_used,
+        ));
+        text.push_str(&usage_table(
+            &format!(
+                "Stale, not used for {} days: {}",
+                self.stale_after_days,
+                self.stale.len()
+            ),
+            &self.stale,
+        ));
+        text.push_str(&count_table(
+            "Created per month",
+            "Month",
+            &self.created_per_month,
+        ));
+        text
+    }
+}
+
+fn new_table(header: &[&str]) -> Table {
+    let mut table = Table::new();
+    table.set_format(*format::consts::FORMAT_NO_LINESEP_WITH_TITLE);
+    table.set_titles(header.iter().copied().collect());
+    table
+}
+
+/// RFC 3339 `timestamp` as local date
+fn local_date(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp).map_or_else(
+        |_| timestamp.to_string(),
+        |time| time.with_timezone(&Local).format("%Y-%m-%d").to_string(),
+    )
+}
+
+#[cfg(test)]
+mod test_stats {
+    use super::*;
+
+    #[test]
+    fn statistics_of_commands() {
+        let now = SystemTime::now();
+        let day = Duration::from_hours(24);
+        let mut favorite = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_tags_raw("vcs")
+            .with_usage(200, now - day * 200);
+        favorite.is_favorite = true;
+        let commands = vec![
+            favorite,
+            HoardCmd::default()
+                .with_name("push")
+                .with_namespace("git")
+                .with_tags_raw("vcs,remote")
+                .with_usage(3, now - day * 3),
+            HoardCmd::default().with_name("ps").with_namespace("docker"),
+            HoardCmd::default()
+                .with_name("logs")
+                .with_namespace("docker")
+                .with_usage(1, now - day),
+        ];
+        let stats = Stats::of(&commands, now, 2, day * 90);
+        assert_eq!(4, stats.commands);
+        assert!((stats.favorite_ratio - 0.25).abs() < f64::EPSILON);
+        assert_eq!(
+            vec![
+                Count {
+                    name: "docker".into(),
+                    commands: 2
+                },
+                Count {
+                    name: "git".into(),
+                    commands: 2
+                }
+            ],
+            stats.namespaces
+        );
+        assert_eq!("vcs", stats.tags[0].name);
+        let most_used: Vec<&str> = stats.most_used.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(vec!["status", "push"], most_used);
+        assert_eq!("ps", stats.never_used[0].name);
+        assert!(stats.never_used[0].last_used.is_none());
+        assert_eq!(1, stats.stale.len());
+        assert_eq!("status", stats.stale[0].name);
+        assert_eq!(1, stats.created_per_month.len());
+        assert_eq!(4, stats.created_per_month[0].commands);
+    }
+
+    #[test]
+    fn tables_and_json() {
+        let now = SystemTime::now();
+        let ps = HoardCmd::default().with_name("ps").with_namespace("docker");
+        let stats = Stats::of(&[ps], now, 10, Duration::from_hours(24));
+        let tables = stats.to_tables();
+        assert!(tables.starts_with("Commands: 1, favorites: 0 (0.0%)\n"));
+        assert!(tables.contains("Never used: 1"));
+        assert!(tables.contains("never"));
+        let json: serde_json::Value = serde_json::from_str(&stats.to_json()).unwrap();
+        assert_eq!(1, json["never_used"].as_array().unwrap().len());
+        assert_eq!(1, json["stale_after_days"]);
+    }
+}
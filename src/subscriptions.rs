@@ -0,0 +1,192 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::core::trove::Trove;
+use crate::core::HoardCmd;
+use crate::util::read_from_uri;
+
+/// Directory inside the hoard config directory where subscribed troves are cached
+const CACHE_DIR: &str = "subscriptions";
+
+const fn default_refresh_interval_hours() -> u64 {
+    24
+}
+
+/// A trove published by someone else, for example your team, which is kept up to date
+///
+/// - `uri`: http(s) URL, `file://` URL or local path of the trove file
+/// - `namespace_prefix`: Put in front of the namespace of every subscribed command, as `<prefix>/<namespace>`
+/// - `refresh_interval_hours`: Age of the cached trove after which it is fetched again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Subscription {
+    pub uri: String,
+    #[serde(default)]
+    pub namespace_prefix: String,
+    #[serde(default = "default_refresh_interval_hours")]
+    pub refresh_interval_hours: u64,
+}
+
+impl Subscription {
+    /// Path of the cached trove file of this subscription inside `hoard_dir`
+    pub fn cache_path(&self, hoard_dir: &Path) -> PathBuf {
+        let file_name: String = self
+            .uri
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        hoard_dir.join(CACHE_DIR).join(format!("{file_name}.yml"))
+    }
+
+    /// Time the cached trove was fetched at, if there is one
+    pub fn fetched_at(&self, hoard_dir: &Path) -> Option<SystemTime> {
+        fs::metadata(self.cache_path(hoard_dir))
+            .and_then(|m| m.modified())
+            .ok()
+    }
+
+    /// `true` if the subscription has never been fetched or its refresh interval has passed
+    pub fn is_stale(&self, hoard_dir: &Path, now: SystemTime) -> bool {
+        let refresh_interval = Duration::from_hours(self.refresh_interval_hours);
+        self.fetched_at(hoard_dir).is_none_or(|fetched_at| {
+            now.duration_since(fetched_at)
+                .is_ok_and(|age| age >= refresh_interval)
+        })
+    }
+
+    fn namespace(&self, namespace: &str) -> String {
+        if self.namespace_prefix.is_empty() {
+            namespace.to_string()
+        } else {
+            format!("{}/{namespace}", self.namespace_prefix)
+        }
+    }
+
+    /// Fetch the subscribed trove and replace the cached one.
+    /// Returns the number of fetched commands
+    pub fn update(&self, hoard_dir: &Path) -> Result<usize> {
+        let content = read_from_uri(&self.uri)?;
+        let mut trove: Trove = serde_yaml::from_str(&content)
+            .map_err(|e| anyhow!("{} is not a valid trove file: {e}", self.uri))?;
+        for command in &mut trove.commands {
+            command.namespace = self.namespace(&command.namespace);
+        }
+        let trove = Trove::from_commands(&trove.commands);
+
+        let cache_path = self.cache_path(hoard_dir);
+        if let Some(cache_dir) = cache_path.parent() {
+            fs::create_dir_all(cache_dir)?;
+        }
+        trove.save_trove_file(&cache_path);
+        Ok(trove.commands.len())
+    }
+
+    /// Load the cached trove of the subscription
+    pub fn load(&self, hoard_dir: &Path) -> Trove {
+        Trove::load_trove_file(&Some(self.cache_path(hoard_dir)))
+    }
+}
+
+/// Load the commands of all subscriptions into one trove.
+/// Stale subscriptions are fetched first. If that fails, the cached trove is used
+pub fn load_subscribed_troves(subscriptions: &[Subscription], hoard_dir: &Path) -> Trove {
+    let now = SystemTime::now();
+    let mut subscribed = Trove::default();
+    for subscription in subscriptions {
+        if subscription.is_stale(hoard_dir, now) {
+            if let Err(e) = subscription.update(hoard_dir) {
+                eprintln!(
+                    "WARNING: Could not update subscription {}: {e}",
+                    subscription.uri
+                );
+            }
+        }
+        subscribed = subscribed.with_read_only_commands(&subscription.load(hoard_dir));
+    }
+    subscribed
+}
+
+/// The subscription whose cached trove contains `command`
+pub fn subscription_of<'a>(
+    subscriptions: &'a [Subscription],
+    hoard_dir: &Path,
+    command: &HoardCmd,
+) -> Option<&'a Subscription> {
+    subscriptions.iter().find(|subscription| {
+        subscription
+            .load(hoard_dir)
+            .commands
+            .iter()
+            .any(|c| c.name == command.name && c.namespace == command.namespace)
+    })
+}
+
+#[cfg(test)]
+mod test_subscriptions {
+    use super::*;
+
+    fn published_trove(dir: &Path) -> PathBuf {
+        let status = HoardCmd::default()
+            .with_name("status")
+            .with_namespace("git")
+            .with_command("git status");
+        let path = dir.join("team.yml");
+        Trove::from_commands(&[status]).save_trove_file(&path);
+        path
+    }
+
+    fn subscription(uri: &Path) -> Subscription {
+        Subscription {
+            uri: uri.to_str().unwrap().to_string(),
+            namespace_prefix: "team".to_string(),
+            refresh_interval_hours: 1,
+        }
+    }
+
+    #[test]
+    fn update_caches_prefixed_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscription = subscription(&published_trove(dir.path()));
+        assert!(subscription.is_stale(dir.path(), SystemTime::now()));
+
+        assert_eq!(1, subscription.update(dir.path()).unwrap());
+        assert!(subscription
+            .cache_path(dir.path())
+            .starts_with(dir.path().join(CACHE_DIR)));
+        assert!(!subscription.is_stale(dir.path(), SystemTime::now()));
+        let an_hour_later = SystemTime::now() + Duration::from_hours(1);
+        assert!(subscription.is_stale(dir.path(), an_hour_later));
+
+        let cached = subscription.load(dir.path());
+        assert_eq!("team/git", cached.commands[0].namespace);
+    }
+
+    #[test]
+    fn load_marks_commands_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let subscription = subscription(&published_trove(dir.path()));
+        let subscriptions = [subscription];
+        let subscribed = load_subscribed_troves(&subscriptions, dir.path());
+        assert_eq!(1, subscribed.commands.len());
+        assert!(subscribed.commands[0].is_read_only);
+
+        let source = subscription_of(&subscriptions, dir.path(), &subscribed.commands[0]);
+        assert_eq!(Some(&subscriptions[0]), source);
+        let own = subscribed.commands[0].clone().with_namespace("git");
+        assert!(subscription_of(&subscriptions, dir.path(), &own).is_none());
+    }
+
+    #[test]
+    fn failed_update_keeps_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let published = published_trove(dir.path());
+        let subscription = subscription(&published);
+        subscription.update(dir.path()).unwrap();
+
+        fs::write(&published, "not: [a trove").unwrap();
+        assert!(subscription.update(dir.path()).is_err());
+        assert_eq!(1, subscription.load(dir.path()).commands.len());
+    }
+}
@@ -1,3 +1,15 @@
+use anyhow::{anyhow, Result};
+use reqwest::Url;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+use std::time::Duration;
+use url::ParseError;
+
+/// Time after which fetching a trove from a remote server is given up
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
 pub fn translate_number_to_nth(count: u16) -> String {
     match count {
         0 => "first".to_string(),
@@ -59,6 +71,63 @@ pub fn split_with_delim(s: &str, delim: &str) -> Vec<String> {
     result
 }
 
+/// Read the content behind `uri`, which is either a http(s) URL, a `file://` URL or a local path
+pub fn read_from_uri(uri: &str) -> Result<String> {
+    match Url::parse(uri) {
+        Ok(url) if url.scheme() == "file" => {
+            let path = url
+                .to_file_path()
+                .map_err(|()| anyhow!("Not a valid file URL: {uri}"))?;
+            Ok(fs::read_to_string(path)?)
+        }
+        Ok(url) if matches!(url.scheme(), "http" | "https") => fetch_url(url),
+        Ok(url) => Err(anyhow!("Unsupported URL scheme: {}", url.scheme())),
+        Err(ParseError::RelativeUrlWithoutBase) => Ok(fs::read_to_string(uri)?),
+        Err(e) => Err(anyhow!("Not a valid URL or file path: {e}")),
+    }
+}
+
+/// Open `path` in `$VISUAL`, `$EDITOR` or else `vi` and wait until the editor is closed.
+/// The variables may contain arguments, like `code --wait`
+pub fn edit_file(path: &Path) -> Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|variable| env::var(variable).ok())
+        .find(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| String::from("vi"));
+    let mut words = editor.split_whitespace();
+    let program = words.next().expect("editor is not empty");
+    let status = Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|e| anyhow!("Could not start the editor [{editor}]: {e}"))?;
+    if !status.success() {
+        return Err(anyhow!("The editor [{editor}] failed with {status}"));
+    }
+    Ok(())
+}
+
+fn fetch_url(url: Url) -> Result<String> {
+    let request = async {
+        reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()?
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await
+    };
+    // hoard runs inside the runtime started in `main`, which can not be blocked on directly
+    let text = match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(request)),
+        Err(_) => tokio::runtime::Runtime::new()?.block_on(request),
+    }?;
+    Ok(text)
+}
+
 #[cfg(test)]
 mod test_commands {
     use super::*;
@@ -98,6 +167,17 @@ mod test_commands {
         assert_eq!(expected, string_find_next(&s, "#", " "));
     }
 
+    #[test]
+    fn read_from_path_and_file_url() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("trove.yml");
+        fs::write(&path, "content").unwrap();
+        assert_eq!("content", read_from_uri(path.to_str().unwrap()).unwrap());
+        let url = Url::from_file_path(&path).unwrap();
+        assert_eq!("content", read_from_uri(url.as_str()).unwrap());
+        assert!(read_from_uri("ftp://example.com/trove.yml").is_err());
+    }
+
     #[test]
     fn test_split_with_delim() {
         let s = String::from("command #param test #param lol");